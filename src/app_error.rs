@@ -0,0 +1,207 @@
+//! The errors of the command line and the GTK form. The errors of the entry itself come from the
+//! library as a [`CreateDesktopError`], this adds the ones only the program runs into.
+use std::{fmt, io};
+use crate::desktop_entry;
+use crate::error::{CreateDesktopError, ParseError};
+
+#[derive(Debug)]
+pub enum AppError {
+    /// The entry couldn't be built, parsed or written, see [`CreateDesktopError`]
+    Entry(CreateDesktopError),
+    /// The program is running on an OS it doesn't support
+    UnsupportedOs(String),
+    /// A global installation was requested without root privileges
+    NeedsRoot,
+    /// The entry would overwrite an existing file without --force
+    FileExists(String),
+    /// There is no installed entry with the given name
+    EntryNotFound(String),
+    /// The entry has validation errors, or warnings with --strict
+    InvalidEntry(Vec<desktop_entry::ValidationIssue>),
+    /// A --from-json file isn't an entry or an array of entries
+    Json(serde_json::Error),
+    /// A --from-toml file isn't [entry] or [[app]] tables, with the key path (ex: `entry.terminal`) when one is to blame
+    Toml { key: Option<String>, error: toml::de::Error },
+    /// Some of the entries of a --from-json or --from-toml file couldn't be created
+    BatchFailed { failed: usize, total: usize },
+    /// The command line couldn't be parsed (ex: an unknown flag or a missing value)
+    Usage(String),
+    /// No installed entry has the name or file name given to --edit, with the ones that come close
+    NoMatchingEntry { name: String, close_matches: Vec<String> },
+    /// --delete wasn't confirmed, so the entry at the path was kept
+    NotDeleted(String),
+    /// A --stdin line that isn't key=value or whose key isn't an entry flag
+    StdinLine { line: usize, message: String },
+    /// The config file couldn't be read, isn't valid TOML or has a key that isn't a default
+    Config { path: String, error: ConfigError },
+}
+
+/// What's wrong with the config file of an [`AppError::Config`]
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file couldn't be read (ex: a --config path that doesn't exist)
+    Read(io::Error),
+    /// The file isn't valid TOML or has a key that isn't a default
+    Parse(Box<toml::de::Error>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(e) => write!(f, "{}", e),
+            ConfigError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Only part of the work was done (ex: some entries of a --from-json file failed)
+pub const EXIT_FAILURE: i32 = 1;
+/// The command line is wrong
+pub const EXIT_USAGE: i32 = 2;
+/// The details of the entry are invalid, EX_DATAERR of sysexits.h like the ones below
+pub const EXIT_DATA: i32 = 65;
+/// There's no installed entry to work on
+pub const EXIT_NO_INPUT: i32 = 66;
+/// The OS isn't supported
+pub const EXIT_UNAVAILABLE: i32 = 69;
+/// The output file can't be created, it's there already
+pub const EXIT_CANT_CREATE: i32 = 73;
+/// A file couldn't be read or written
+pub const EXIT_IO: i32 = 74;
+/// Root is needed, or a file can't be read or written by this user
+pub const EXIT_NO_PERMISSION: i32 = 77;
+/// The config file or the environment (ex: no home directory) is wrong
+pub const EXIT_CONFIG: i32 = 78;
+
+/// The exit codes and what they mean, as listed by --help
+pub const EXIT_CODES: [(i32, &str); 10] = [
+    (0, "The entry was created, or the command did what it was asked"),
+    (EXIT_FAILURE, "Only part of it was done: a --validate file has errors, some entries of a batch failed or --delete wasn't confirmed"),
+    (EXIT_USAGE, "The command line is wrong (ex: an unknown flag or a --stdin line that isn't key=value)"),
+    (EXIT_DATA, "The entry is invalid (ex: no Name, a bad value or a --from-json file that isn't JSON)"),
+    (EXIT_NO_INPUT, "There's no installed entry to --edit, --delete or --show"),
+    (EXIT_UNAVAILABLE, "The OS isn't supported"),
+    (EXIT_CANT_CREATE, "The file is there already, use --force to overwrite it"),
+    (EXIT_IO, "A file couldn't be read or written"),
+    (EXIT_NO_PERMISSION, "Permission denied (ex: --global without sudo)"),
+    (EXIT_CONFIG, "The config file can't be used or there's no home directory"),
+];
+
+impl AppError {
+    /// Exit code reported to the shell, grouped by what went wrong so scripts can tell failures apart
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::Entry(e) => match e {
+                CreateDesktopError::Io(e) if e.kind() == io::ErrorKind::PermissionDenied => EXIT_NO_PERMISSION,
+                CreateDesktopError::Io(_) => EXIT_IO,
+                CreateDesktopError::NoHomeDir => EXIT_CONFIG,
+                CreateDesktopError::MissingName
+                | CreateDesktopError::MissingUrl
+                | CreateDesktopError::UnexpectedUrl(_)
+                | CreateDesktopError::InvalidField { .. }
+                | CreateDesktopError::ConflictingKeys { .. }
+                | CreateDesktopError::Parse(_)
+                | CreateDesktopError::ValidationFailed(_)
+                | CreateDesktopError::UnknownType(_)
+                | CreateDesktopError::DuplicateKey(_) => EXIT_DATA,
+            },
+            AppError::BatchFailed { .. } | AppError::NotDeleted(_) => EXIT_FAILURE,
+            AppError::Usage(_) | AppError::StdinLine { .. } => EXIT_USAGE,
+            AppError::InvalidEntry(_) | AppError::Json(_) | AppError::Toml { .. } => EXIT_DATA,
+            AppError::EntryNotFound(_) | AppError::NoMatchingEntry { .. } => EXIT_NO_INPUT,
+            AppError::UnsupportedOs(_) => EXIT_UNAVAILABLE,
+            AppError::FileExists(_) => EXIT_CANT_CREATE,
+            AppError::NeedsRoot => EXIT_NO_PERMISSION,
+            AppError::Config { .. } => EXIT_CONFIG,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Entry(e) => write!(f, "{}", e),
+            AppError::UnsupportedOs(os) => write!(f, "This program is not supported on {}", os),
+            AppError::NeedsRoot => write!(f, "Global installation requires root privileges. Please run with sudo."),
+            AppError::FileExists(path) => write!(f, "{} already exists, use --force to overwrite it", path),
+            AppError::EntryNotFound(path) => write!(f, "No desktop entry found at {}", path),
+            AppError::InvalidEntry(issues) => write!(
+                f,
+                "{}",
+                issues.iter().map(|issue| issue.to_string()).collect::<Vec<_>>().join("; ")
+            ),
+            AppError::Json(e) => write!(f, "Failed to read the JSON entries, {}", e),
+            AppError::Toml { key: Some(key), error } => write!(f, "Failed to read {} of the TOML entries, {}", key, error),
+            AppError::Toml { key: None, error } => write!(f, "Failed to read the TOML entries, {}", error),
+            AppError::BatchFailed { failed, total } => write!(f, "{} of {} entries could not be created", failed, total),
+            AppError::Usage(message) => write!(f, "{}", message),
+            AppError::NoMatchingEntry { name, close_matches } if close_matches.is_empty() => {
+                write!(f, "No installed entry named {:?}, see --list", name)
+            }
+            AppError::NoMatchingEntry { name, close_matches } => {
+                write!(f, "No installed entry named {:?}, did you mean {}?", name, close_matches.join(", "))
+            }
+            AppError::NotDeleted(path) => write!(f, "Didn't delete {}, use --yes to delete it without being asked", path),
+            AppError::StdinLine { line, message } => write!(f, "Failed to read line {} of standard input, {}", line, message),
+            AppError::Config { path, error } => write!(f, "Failed to read the config file {}, {}", path, error),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Entry(e) => e.source(),
+            AppError::Json(e) => Some(e),
+            AppError::Toml { error, .. } => Some(error),
+            AppError::Config { error: ConfigError::Read(e), .. } => Some(e),
+            AppError::Config { error: ConfigError::Parse(e), .. } => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<CreateDesktopError> for AppError {
+    fn from(e: CreateDesktopError) -> Self {
+        AppError::Entry(e)
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(e: io::Error) -> Self {
+        AppError::Entry(CreateDesktopError::Io(e))
+    }
+}
+
+impl From<ParseError> for AppError {
+    fn from(e: ParseError) -> Self {
+        AppError::Entry(CreateDesktopError::Parse(e))
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Json(e)
+    }
+}
+
+impl From<toml::de::Error> for AppError {
+    fn from(e: toml::de::Error) -> Self {
+        AppError::Toml { key: None, error: e }
+    }
+}
+
+impl From<serde_path_to_error::Error<toml::de::Error>> for AppError {
+    fn from(e: serde_path_to_error::Error<toml::de::Error>) -> Self {
+        let key = e.path().to_string();
+        AppError::Toml { key: (key != ".").then_some(key), error: e.into_inner() }
+    }
+}
+
+impl From<dialoguer::Error> for AppError {
+    fn from(e: dialoguer::Error) -> Self {
+        match e {
+            dialoguer::Error::IO(e) => AppError::Entry(CreateDesktopError::Io(e)),
+        }
+    }
+}
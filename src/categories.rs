@@ -0,0 +1,85 @@
+/// Main categories from the freedesktop.org Desktop Menu Specification
+pub const MAIN_CATEGORIES: [&str; 13] = [
+    "AudioVideo", "Audio", "Video", "Development", "Education", "Game", "Graphics",
+    "Network", "Office", "Science", "Settings", "System", "Utility",
+];
+
+/// Additional and reserved categories from the freedesktop.org Desktop Menu Specification
+pub const ADDITIONAL_CATEGORIES: [&str; 131] = [
+    "Building", "Debugger", "IDE", "GUIDesigner", "Profiling", "RevisionControl", "Translation",
+    "Calendar", "ContactManagement", "Database", "Dictionary", "Chart", "Email", "Finance",
+    "FlowChart", "PDA", "ProjectManagement", "Presentation", "Spreadsheet", "WordProcessor",
+    "2DGraphics", "VectorGraphics", "RasterGraphics", "3DGraphics", "Scanning", "OCR",
+    "Photography", "Publishing", "Viewer", "TextTools", "DesktopSettings", "HardwareSettings",
+    "Printing", "PackageManager", "Dialup", "InstantMessaging", "Chat", "IRCClient", "Feed",
+    "FileTransfer", "HamRadio", "News", "P2P", "RemoteAccess", "Telephony", "TelephonyTools",
+    "VideoConference", "WebBrowser", "WebDevelopment", "Midi", "Mixer", "Sequencer", "Tuner",
+    "TV", "AudioVideoEditing", "Player", "Recorder", "DiscBurning", "ActionGame",
+    "AdventureGame", "ArcadeGame", "BoardGame", "BlocksGame", "CardGame", "KidsGame",
+    "LogicGame", "RolePlaying", "Shooter", "Simulation", "SportsGame", "StrategyGame", "Art",
+    "Construction", "Music", "Languages", "ArtificialIntelligence", "Astronomy", "Biology",
+    "Chemistry", "ComputerScience", "DataVisualization", "Economy", "Electricity", "Geography",
+    "Geology", "Geoscience", "History", "Humanities", "ImageProcessing", "Literature", "Maps",
+    "Math", "NumericalAnalysis", "MedicalSoftware", "Physics", "Robotics", "Spirituality",
+    "Sports", "ParallelComputing", "Amusement", "Archiving", "Compression", "Electronics",
+    "Emulator", "Engineering", "FileTools", "FileManager", "TerminalEmulator", "Filesystem",
+    "Monitor", "Security", "Accessibility", "Calculator", "Clock", "TextEditor",
+    "Documentation", "Adult", "Core", "KDE", "GNOME", "XFCE", "DDE", "GTK", "Qt", "Motif",
+    "Java", "ConsoleOnly", "Screensaver", "TrayIcon", "Applet", "Shell",
+];
+
+/// Desktop environments registered in the Desktop Menu Specification, for OnlyShowIn / NotShowIn
+pub const DESKTOP_ENVIRONMENTS: [&str; 20] = [
+    "GNOME", "GNOME-Classic", "GNOME-Flashback", "KDE", "LXDE", "LXQt", "MATE", "Razor", "ROX",
+    "TDE", "Unity", "XFCE", "EDE", "Cinnamon", "Pantheon", "Budgie", "Enlightenment", "DDE",
+    "Endless", "Old",
+];
+
+/// Returns the environments in a semicolon-separated list that aren't registered,
+/// vendor specific ones starting with `X-` are always accepted
+pub fn unknown_environments(environments: &str) -> Vec<String> {
+    environments
+        .split(';')
+        .map(|environment| environment.trim())
+        .filter(|environment| !environment.is_empty())
+        .filter(|environment| !environment.starts_with("X-") && !DESKTOP_ENVIRONMENTS.contains(environment))
+        .map(|environment| environment.to_string())
+        .collect()
+}
+
+/// Splits a list of categories given as `Development;IDE;` or `Development,IDE`
+pub fn parse_categories(categories: &str) -> Vec<String> {
+    categories
+        .split([';', ','])
+        .map(|category| category.trim())
+        .filter(|category| !category.is_empty())
+        .map(|category| category.to_string())
+        .collect()
+}
+
+/// Checks each category against the registry.
+/// Vendor specific categories starting with `X-` are always accepted.
+pub fn validate_categories(categories: &[String]) -> Result<(), Vec<String>> {
+    let unknown: Vec<String> = categories
+        .iter()
+        .filter(|category| !is_registered_category(category))
+        .cloned()
+        .collect();
+
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(unknown)
+    }
+}
+
+/// Menus are built from the main categories, additional ones only refine where an entry goes
+pub fn has_main_category(categories: &[String]) -> bool {
+    categories.iter().any(|category| MAIN_CATEGORIES.contains(&category.as_str()))
+}
+
+fn is_registered_category(category: &str) -> bool {
+    category.starts_with("X-")
+        || MAIN_CATEGORIES.contains(&category)
+        || ADDITIONAL_CATEGORIES.contains(&category)
+}
@@ -0,0 +1,547 @@
+//! The command line, parsed into a typed [`CliArgs`]. The --help output is generated from the
+//! same definitions, so every flag is documented where it's declared.
+use std::fmt;
+use std::path::{Path, PathBuf};
+use clap::{Args, Command, CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap_complete::Shell;
+use crate::{desktop_entry, flags, path};
+use crate::config::{Config, Scope};
+use crate::app_error::AppError;
+use crate::error::CreateDesktopError;
+
+#[derive(Parser, Debug, Default)]
+#[command(
+    name = "create-desktop-file",
+    about = "create-desktop-file is a simple tool to create .desktop files for Linux.\n\
+    By default it will open a GUI app, however this can also run in Terminal by specifying the below flags/arguments",
+    disable_help_flag = true,
+    disable_version_flag = true,
+    disable_help_subcommand = true,
+    next_line_help = true,
+    // A flag given twice keeps its last value, like most tools
+    args_override_self = true,
+)]
+pub struct CliArgs {
+    /// Install .desktop file locally in $XDG_DATA_HOME/applications/ (~/.local/share/applications/ by default)
+    #[arg(long, short = 'l')]
+    pub local: bool,
+    /// Install .desktop file globally in /usr/share/applications/
+    #[arg(long, short = 'g')]
+    pub global: bool,
+    /// (With --global) Install into <PREFIX>/share/applications/ instead (ex: --prefix /usr/local, a relative prefix is from the current directory), still requires sudo
+    #[arg(long, value_name = "PREFIX", requires = "global")]
+    pub prefix: Option<PathBuf>,
+    /// Write the .desktop file to this path instead, ignoring --local and --global (ex: -o ./Foo.desktop).
+    /// A directory (ex: -o dist/) gets the usual file name (see --filename) and - prints it like --stdout
+    #[arg(long, short = 'o', value_name = "PATH")]
+    pub output: Option<PathBuf>,
+    /// Print the .desktop file to standard output instead of writing it
+    #[arg(long)]
+    pub stdout: bool,
+    /// Name the file <FILENAME>.desktop instead of after the slugified name (ex: "My App" is written to my-app.desktop)
+    #[arg(long)]
+    pub filename: Option<String>,
+    /// Ask for the details in the terminal with prompts (defaults, a yes/no for Terminal and a list of categories to pick from)
+    #[arg(long)]
+    pub interactive: bool,
+    /// Print the .desktop file and the path it would be written to, without writing anything (also works with the prompts, --from-json and --from-toml, and with --global without sudo)
+    #[arg(long)]
+    pub dry_run: bool,
+    /// List the installed .desktop files in ~/.local/share/applications, or /usr/share/applications with --global,
+    /// with their Name, Exec and whether the program Exec starts is installed
+    #[arg(long)]
+    pub list: bool,
+    /// (With --list) List both the local and the global .desktop files
+    #[arg(long, requires = "list")]
+    pub all: bool,
+    /// Delete an installed .desktop file by name, file name or path (ex: --delete "My App" removes my-app.desktop) after showing it and
+    /// asking to confirm, use --global for the global one (requires sudo)
+    #[arg(long, value_name = "NAME")]
+    pub delete: Option<String>,
+    /// (With --delete) Delete without asking for confirmation first
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+    /// Print an installed .desktop file by name or file name (ex: --export "My App" prints my-app.desktop), use --global for the global one
+    #[arg(long, value_name = "NAME")]
+    pub export: Option<String>,
+    /// (With --export) Print the entry as JSON instead, in the format --from-json reads
+    #[arg(long)]
+    pub json: bool,
+    /// Print the entry as JSON in the format --from-json reads, plus the "destination" it goes to and whether it was "written",
+    /// instead of the usual messages. With --dry-run it's a preview, with --from-json or --from-toml an array of the entries created
+    #[arg(long)]
+    pub export_json: bool,
+    /// Print where an installed .desktop file is and its contents, by name, file name or path (ex: --show firefox).
+    /// The local directory is looked in first, and a file in both is shown twice noting that the local one wins
+    #[arg(long, value_name = "NAME")]
+    pub show: Option<String>,
+    /// (With --show) Print the keys and their unescaped values instead of the file as is
+    #[arg(long, requires = "show")]
+    pub parsed: bool,
+    /// Load an existing .desktop file, given as a path or the name or file name of an installed entry, apply
+    /// the other flags to it and write it back keeping every other key (ex: --edit "My App" --comment "New comment").
+    /// Without other flags the GUI opens with the form filled in from the file, with --interactive the prompts do
+    #[arg(long, value_name = "ENTRY")]
+    pub edit: Option<PathBuf>,
+    /// Create a .desktop file from a JSON object, or one per object of an array, in the --local or --global directory, - reads standard input
+    /// (ex: {"name": "Foo", "exec": "/usr/bin/foo", "icon": "foo", "categories": ["Utility"], "terminal": false}, see --export --json for every key).
+    /// Unknown keys are warned about, entry flags given alongside override the JSON values and in an array the entries that fail are skipped.
+    /// The entries are written into the applications directory, so --stdout and --output can't be used with it (--dry-run previews them)
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["stdout", "output"])]
+    pub from_json: Option<PathBuf>,
+    /// Read the details from standard input instead of asking for them, one key=value line per flag of the entry
+    /// (ex: printf 'name=Foo\nexec=/usr/bin/foo\nterminal=false\n' | create-desktop-file --stdin).
+    /// Blank lines and # comments are skipped, flags on the command line win over the lines
+    #[arg(long, conflicts_with_all = ["interactive", "from_json", "from_toml"])]
+    pub stdin: bool,
+    /// Same as --from-json for a TOML file with an [entry] table, or one [[entry]] (or [[app]]) table per entry (ex: [entry] name = "Foo" exec = "/usr/bin/foo").
+    /// Actions and localized names are nested tables (ex: [[entry.actions]] or [entry.localized_names])
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["stdout", "output"])]
+    pub from_toml: Option<PathBuf>,
+    /// Check the written (or with --stdout the printed) .desktop file with desktop-file-validate, exits non-zero if it reports errors.
+    /// Given files or directories instead (ex: --validate ~/.local/share/applications) it checks those .desktop files against
+    /// the spec, printing the problems with their line and exiting 1 if any file has errors
+    #[arg(long, num_args = 0.., value_name = "FILE")]
+    pub validate: Option<Vec<PathBuf>>,
+    /// Run update-desktop-database and xdg-desktop-menu forceupdate afterwards, so launchers pick up the entry
+    /// and its MimeType associations. This is the default for --global installs
+    #[arg(long, visible_alias = "update-db", overrides_with = "no_refresh")]
+    pub refresh: bool,
+    /// Don't refresh the desktop database, not even after a --global install
+    #[arg(long, overrides_with = "refresh")]
+    pub no_refresh: bool,
+    /// Treat warnings (ex: unregistered categories, a missing icon file) as errors and don't write the entry
+    #[arg(long)]
+    pub strict: bool,
+    /// Don't check that the program Exec runs exists and is executable
+    #[arg(long)]
+    pub no_verify: bool,
+    /// Make the program Exec runs executable when it's a path to a file you own (ex: a script you just wrote).
+    /// A program looked up on PATH is left alone with a warning
+    #[arg(long)]
+    pub chmod_exec: bool,
+    /// Leave out the "# Created by create-desktop-file vX on <date>" comment written above the entry
+    #[arg(long)]
+    pub no_header: bool,
+    /// Overwrite the .desktop file if it already exists, without it you're asked when run from a terminal
+    #[arg(long)]
+    pub force: bool,
+    /// Print nothing on success (ex: the "Desktop entry created at" line), errors and warnings still go to standard error
+    #[arg(long, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Explain each step on standard error: the directory and file name picked, the validation results and the full entry
+    #[arg(long)]
+    pub verbose: bool,
+    /// Read the defaults (categories, terminal, type, validate and scope) from this file instead of
+    /// $XDG_CONFIG_HOME/create-desktop-file/config.toml (ex: categories = ["Development"] and scope = "global")
+    #[arg(long, value_name = "FILE", conflicts_with = "no_config")]
+    pub config: Option<PathBuf>,
+    /// Ignore the config file, every detail not given is left out as without one
+    #[arg(long)]
+    pub no_config: bool,
+    /// The defaults read from the config file, for the details the flags leave out
+    #[arg(skip)]
+    pub defaults: Config,
+    /// Warn about unknown flags (ex: a typo like --exepath) and carry on without them and the value after them, instead of stopping
+    #[arg(long)]
+    pub lenient: bool,
+    /// The unknown flags --lenient left out, for the caller to warn about
+    #[arg(skip)]
+    pub ignored_flags: Vec<IgnoredFlag>,
+    /// Print a completion script for the shell to standard output (ex: --generate-completions bash > ~/.local/share/bash-completion/completions/create-desktop-file)
+    #[arg(long, hide = true, value_name = "SHELL")]
+    pub generate_completions: Option<Shell>,
+    /// Show version information
+    #[arg(long, short = 'V')]
+    pub version: bool,
+    /// Show this help message
+    #[arg(long, short = 'h')]
+    pub help: bool,
+    /// Set the name of the .desktop file, if not used, it will ask you specifically for the details
+    #[arg(long, short = 'n', help_heading = "Entry details (require --name)")]
+    pub name: Option<String>,
+    #[command(flatten)]
+    pub entry: EntryArgs,
+    /// Folded into the flags it stands for once parsed, see [`CliArgs::from_args`]
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+/// Commands for the flags that pick what to do, the flags are accepted before or after them
+/// (ex: `delete "My App" --yes` is `--delete "My App" --yes`)
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum Commands {
+    /// Create a .desktop file, the same as giving no command
+    Create,
+    /// List the installed .desktop files, the same as --list
+    List,
+    /// Delete an installed .desktop file, the same as --delete <NAME>
+    Delete {
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+    /// Edit a .desktop file or installed entry, the same as --edit <ENTRY>
+    Edit {
+        #[arg(value_name = "ENTRY")]
+        entry: PathBuf,
+    },
+}
+
+/// The details of the entry itself, given alongside --name
+#[derive(Args, Debug, Default, Clone, PartialEq)]
+#[command(next_help_heading = "Entry details (require --name)")]
+pub struct EntryArgs {
+    /// Set the generic name of the application (Text Editor, Web Browser, etc.)
+    #[arg(long)]
+    pub generic_name: Option<String>,
+    /// Set the comment of the .desktop file, the words up to the next flag don't need quoting
+    #[arg(long, short = 'c', num_args = 1..)]
+    pub comment: Option<Vec<String>>,
+    /// Add a translated name for a locale, can be repeated (ex: --name-locale fr "Éditeur" or --name-locale de:Rechner)
+    #[arg(long, num_args = 1..=2, value_names = ["LOCALE", "NAME"])]
+    pub name_locale: Vec<String>,
+    /// Add a translated generic name for a locale, can be repeated (ex: --generic-name-locale de "Texteditor")
+    #[arg(long, num_args = 1..=2, value_names = ["LOCALE", "GENERIC_NAME"])]
+    pub generic_name_locale: Vec<String>,
+    /// Add a translated comment for a locale, can be repeated (ex: --comment-locale de "Texte bearbeiten" or --comment-locale fr:Calculatrice)
+    #[arg(long, num_args = 1..=2, value_names = ["LOCALE", "COMMENT"])]
+    pub comment_locale: Vec<String>,
+    /// Set the command to execute
+    #[arg(long, short = 'e', visible_alias = "exec")]
+    pub exec_path: Option<String>,
+    /// Only show the entry when this program is installed (ex: --try-exec firefox)
+    #[arg(long)]
+    pub try_exec: Option<String>,
+    /// Append a field code to Exec so the application can open files or URLs: f (a file), F (files), u (a URL), U (URLs)
+    #[arg(long)]
+    pub field_code: Option<String>,
+    /// Set the icon to use
+    #[arg(long, short = 'i', visible_alias = "icon")]
+    pub icon_path: Option<String>,
+    /// Set the working directory the command is run in (Path=)
+    #[arg(long, visible_alias = "path")]
+    pub working_dir: Option<String>,
+    /// Run the command in Terminal: true/false, yes/no or 1/0 (default false)
+    #[arg(long, short = 't', visible_alias = "terminal")]
+    pub terminal_app: Option<String>,
+    /// Set the type of the .desktop file: Application (the default), Link or Directory
+    #[arg(long, visible_alias = "type")]
+    pub app_type: Option<String>,
+    /// Set the URL a Link entry opens, required with --app-type Link (ex: https://example.com)
+    #[arg(long)]
+    pub url: Option<String>,
+    /// Set the categories of the .desktop file (Utility, Game, etc.)
+    #[arg(long, short = 'C')]
+    pub categories: Option<String>,
+    /// Only show the entry in these desktop environments (ex: KDE;XFCE;)
+    #[arg(long)]
+    pub only_show_in: Option<String>,
+    /// Hide the entry in these desktop environments, can't be used with --only-show-in (ex: GNOME;)
+    #[arg(long)]
+    pub not_show_in: Option<String>,
+    /// Set the search keywords of the .desktop file (editor;text; or "editor, text")
+    #[arg(long)]
+    pub keywords: Option<String>,
+    /// Set the MIME types the application can open (text/plain;image/png;)
+    #[arg(long)]
+    pub mime_type: Option<String>,
+    /// Show a startup notification while the application launches (true/false/yes/no)
+    #[arg(long)]
+    pub startup_notify: Option<String>,
+    /// Set the window class used to match the running application to this entry
+    #[arg(long, visible_alias = "wm-class")]
+    pub startup_wm_class: Option<String>,
+    /// The reverse DNS application id (ex: org.example.MyApp), used as the file name and as StartupWMClass unless those are given
+    #[arg(long)]
+    pub app_id: Option<String>,
+    /// Hide the application from menus while keeping the entry (NoDisplay=true), true on its own or ex: --no-display false
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub no_display: Option<String>,
+    /// Mask an entry of the same name, as if it was deleted (Hidden=true), true on its own or ex: --hidden false
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub hidden: Option<String>,
+    /// Ask the desktop to launch the application on the dedicated graphics card (ex: for games)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub prefers_non_default_gpu: Option<String>,
+    /// Launch through D-Bus activation, name the file after the bus name with --app-id (ex: --app-id org.example.App)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true", value_name = "BOOL")]
+    pub dbus_activatable: Option<String>,
+    /// Override the Desktop Entry Specification version written as the Version key (default: 1.5)
+    #[arg(long, visible_alias = "version-field")]
+    pub spec_version: Option<String>,
+    /// Leave the Version key out of the .desktop file
+    #[arg(long)]
+    pub no_spec_version: bool,
+    /// Add a vendor extension key, can be repeated (ex: --extra-key X-GNOME-UsesNotifications=true)
+    #[arg(long, value_name = "KEY=VALUE")]
+    pub extra_key: Vec<String>,
+    /// Add a right-click action, can be repeated ("new-window:Name=New Window:Exec=/usr/bin/foo --new-window")
+    /// or in short, id:Name:Exec ("new-window:New Window:/usr/bin/foo --new-window")
+    #[arg(long)]
+    pub action: Vec<String>,
+}
+
+impl CliArgs {
+    /// Prints what was done (ex: where the entry was written), unless --quiet or --export-json
+    pub fn report(&self, message: impl fmt::Display) {
+        if !self.quiet && !self.export_json {
+            println!("{}", message);
+        }
+    }
+
+    /// Prints a decision along the way with --verbose, on standard error so it doesn't mix with --stdout
+    pub fn explain(&self, message: impl fmt::Display) {
+        if self.verbose {
+            eprintln!("Info: {}", message);
+        }
+    }
+
+    /// The command line definition, with every flag usable after a command too
+    pub fn cli() -> Command {
+        CliArgs::command().mut_args(|arg| arg.global(true))
+    }
+
+    /// Parses a full command line, `args[0]` being the program name. A command sets the flag
+    /// it stands for, so the rest of the program only looks at the flags
+    pub fn from_args(args: &[String]) -> Result<Self, AppError> {
+        let mut args = attach_dashed_values(args);
+        let lenient = args.iter().any(|arg| arg == flags::LENIENT);
+        let mut ignored_flags = Vec::new();
+        let matches = loop {
+            match CliArgs::cli().try_get_matches_from(&args) {
+                Ok(matches) => break matches,
+                Err(e) if lenient && e.kind() == ErrorKind::UnknownArgument => {
+                    ignored_flags.push(remove_unknown_flag(&mut args, &e).ok_or_else(|| usage_error(e))?);
+                }
+                Err(e) => return Err(usage_error(e)),
+            }
+        };
+        let mut cli_args = CliArgs::from_arg_matches(&matches).map_err(usage_error)?;
+        cli_args.ignored_flags = ignored_flags;
+        // A relative prefix is from where it was run (ex: --prefix build), not from the home directory
+        if let Some(prefix) = &cli_args.prefix {
+            cli_args.prefix = Some(std::path::absolute(prefix)?);
+        }
+        match cli_args.command.take() {
+            Some(Commands::List) => cli_args.list = true,
+            Some(Commands::Delete { name }) => cli_args.delete = Some(name),
+            Some(Commands::Edit { entry }) => cli_args.edit = Some(entry),
+            Some(Commands::Create) | None => {}
+        }
+        Ok(cli_args)
+    }
+
+    /// Reads the defaults from --config, or from the `default_config` file, unless --no-config is given.
+    /// Only done once --help and --version are out of the way, so a broken config file doesn't stop them
+    pub fn load_defaults(&mut self, default_config: Option<&Path>) -> Result<(), AppError> {
+        if !self.no_config {
+            self.defaults = Config::load(self.config.as_deref(), default_config)?;
+        }
+        Ok(())
+    }
+
+    /// Parses `args` with the --stdin `answers` as flags. A missing name isn't asked for, since
+    /// standard input has already been read
+    pub fn from_args_and_answers(args: &[String], answers: &str) -> Result<Self, AppError> {
+        let mut full_args = args[..1.min(args.len())].to_vec();
+        full_args.extend(answer_flags(answers)?);
+        full_args.extend(args.iter().skip(1).cloned());
+        let cli_args = CliArgs::from_args(&full_args)?;
+        if cli_args.name.is_none() && cli_args.edit.is_none() {
+            return Err(CreateDesktopError::MissingName.into());
+        }
+        Ok(cli_args)
+    }
+
+    /// The global applications directory, moved under --prefix when it's given
+    pub fn global_dir(&self, default: &str) -> String {
+        match &self.prefix {
+            Some(prefix) => path::prefixed_applications_dir(prefix),
+            None => default.to_string(),
+        }
+    }
+
+    /// Whether the entry is installed globally, with --global or the config file's scope when --local isn't given
+    pub fn is_global(&self) -> bool {
+        self.global || (!self.local && self.defaults.scope == Some(Scope::Global))
+    }
+
+    /// Whether the desktop database is refreshed after writing, by default only when it went into the global directory
+    pub fn refreshes(&self, into_global_dir: bool) -> bool {
+        !self.no_refresh && (self.refresh || into_global_dir)
+    }
+
+    /// Whether the written entry is checked with desktop-file-validate, --validate without files
+    pub fn validates_output(&self) -> bool {
+        match &self.validate {
+            Some(paths) => paths.is_empty(),
+            None => self.defaults.validate,
+        }
+    }
+
+    /// The files and directories --validate checks on their own, `None` when it's checking the written entry
+    pub fn files_to_validate(&self) -> Option<&[PathBuf]> {
+        self.validate.as_deref().filter(|paths| !paths.is_empty())
+    }
+
+    /// Whether any detail of the entry is given, those need --name (or --edit) to apply to
+    pub fn has_details(&self) -> bool {
+        self.entry != EntryArgs::default()
+    }
+
+    /// The completion script of every flag for `shell`, completing the command `bin_name`
+    pub fn completions(shell: Shell, bin_name: &str) -> String {
+        let mut script = Vec::new();
+        clap_complete::generate(shell, &mut CliArgs::cli(), bin_name, &mut script);
+        String::from_utf8_lossy(&script).into_owned()
+    }
+
+    /// The help message, with `bin_name` in the usage line
+    pub fn help(bin_name: &str) -> String {
+        CliArgs::cli().bin_name(bin_name).render_help().to_string()
+    }
+}
+
+impl EntryArgs {
+    /// The --app-id, checked to be a reverse DNS name
+    pub fn app_id(&self) -> Result<Option<&str>, AppError> {
+        match self.app_id.as_deref().map(str::trim) {
+            Some(app_id) if !desktop_entry::is_reverse_dns_name(app_id) => Err(CreateDesktopError::invalid_field(flags::APP_ID, app_id).into()),
+            app_id => Ok(app_id),
+        }
+    }
+
+    /// --startup-wm-class, or the --app-id when it isn't given since the window class usually matches it
+    pub fn startup_wm_class(&self) -> Result<Option<String>, AppError> {
+        match &self.startup_wm_class {
+            Some(startup_wm_class) => Ok(Some(startup_wm_class.clone())),
+            None => Ok(self.app_id()?.map(str::to_string)),
+        }
+    }
+
+    /// The words given to --comment, joined back into one value
+    pub fn comment(&self) -> Option<String> {
+        self.comment.as_ref().map(|words| words.join(" "))
+    }
+
+    /// The --terminal-app `value`, None when it's empty
+    pub fn terminal(value: &str) -> Result<Option<bool>, AppError> {
+        desktop_entry::parse_terminal(value)
+            .map_err(|_| CreateDesktopError::invalid_field(flags::TERMINAL_APP, value.trim()).into())
+    }
+
+    /// The --no-display, --hidden, --prefers-non-default-gpu or --dbus-activatable `value`,
+    /// None when the switch isn't given
+    pub fn switch(name: &str, value: &Option<String>) -> Result<Option<bool>, AppError> {
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        match value.to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" => Ok(Some(true)),
+            "false" | "0" | "no" => Ok(Some(false)),
+            _ => Err(CreateDesktopError::invalid_field(name, value).into()),
+        }
+    }
+
+    /// The locale and value of every occurrence of a locale flag, given either as two
+    /// arguments (`fr "Éditeur"`) or as one (`fr:Éditeur`)
+    pub fn locale_pairs(name: &str, values: &[String]) -> Result<Vec<(String, String)>, AppError> {
+        let mut pairs = Vec::new();
+        let mut values = values.iter();
+        while let Some(first) = values.next() {
+            match first.split_once(':') {
+                Some((locale, value)) if desktop_entry::is_valid_locale(locale) => pairs.push((locale.to_string(), value.to_string())),
+                _ => {
+                    let value = values.next().ok_or_else(|| CreateDesktopError::invalid_field(name, first))?;
+                    pairs.push((first.to_string(), value.to_string()));
+                }
+            }
+        }
+        Ok(pairs)
+    }
+}
+
+/// The --stdin `key=value` lines as flags (name=Foo is --name=Foo), the keys being the long flags of the entry
+fn answer_flags(answers: &str) -> Result<Vec<String>, AppError> {
+    let mut answer_flags = Vec::new();
+    for (index, line) in answers.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| AppError::StdinLine { line: index + 1, message };
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(error(format!("expected key=value, found {:?}", line)));
+        };
+        let long = key.trim().replace('_', "-");
+        if !is_entry_flag(&long) {
+            return Err(error(format!("unknown key {:?}", key.trim())));
+        }
+        answer_flags.push(format!("--{}={}", long, value.trim()));
+    }
+    Ok(answer_flags)
+}
+
+/// Whether `long` (ex: exec-path or its alias exec) is --name or one of the [`EntryArgs`], read from
+/// their definitions so a new entry flag is a --stdin key too
+pub fn is_entry_flag(long: &str) -> bool {
+    let command = EntryArgs::augment_args(Command::new("entry"));
+    let name = CliArgs::command();
+    command.get_arguments()
+        .chain(name.get_arguments().filter(|arg| arg.get_id() == "name"))
+        .any(|arg| arg.get_long() == Some(long) || arg.get_all_aliases().is_some_and(|aliases| aliases.contains(&long)))
+}
+
+/// A value starting with a dash (ex: --comment "--verbose shows more") would be taken for a flag,
+/// but flags have no spaces in them, so such a value is attached to the flag before it (--comment=...)
+fn attach_dashed_values(args: &[String]) -> Vec<String> {
+    let mut attached: Vec<String> = Vec::with_capacity(args.len());
+    for arg in args {
+        let looks_like_value = arg.starts_with('-') && arg.split('=').next().is_some_and(|flag| flag.contains(char::is_whitespace));
+        match attached.last_mut() {
+            Some(flag) if looks_like_value && flag.starts_with('-') && !flag.contains('=') => {
+                flag.push('=');
+                flag.push_str(arg);
+            }
+            _ => attached.push(arg.clone()),
+        }
+    }
+    attached
+}
+
+/// An unknown flag --lenient left out, with the value after it that went with it
+#[derive(Debug, Clone, PartialEq)]
+pub struct IgnoredFlag {
+    pub flag: String,
+    pub value: Option<String>,
+}
+
+impl fmt::Display for IgnoredFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "ignored the unknown flag {} and discarded the value after it, {:?}", self.flag, value),
+            None => write!(f, "ignored the unknown flag {}", self.flag),
+        }
+    }
+}
+
+/// Takes the unknown flag clap stopped at out of `args`, with the value after it when there's one
+/// that isn't a flag itself, since it would be read as an argument otherwise. Returns what was
+/// taken out, or `None` when the flag can't be found
+fn remove_unknown_flag(args: &mut Vec<String>, e: &clap::Error) -> Option<IgnoredFlag> {
+    let Some(ContextValue::String(unknown)) = e.get(ContextKind::InvalidArg) else {
+        return None;
+    };
+    let index = args.iter().skip(1).position(|arg| arg == unknown || arg.starts_with(&format!("{}=", unknown)))? + 1;
+    let flag = args.remove(index);
+    let value = (!flag.contains('=') && args.get(index).is_some_and(|value| !value.starts_with('-')))
+        .then(|| args.remove(index));
+    Some(IgnoredFlag { flag, value })
+}
+
+/// Keeps clap's message, without its "error: " prefix since it's printed after ours
+fn usage_error(e: clap::Error) -> AppError {
+    let message = e.to_string();
+    AppError::Usage(message.trim_start_matches("error: ").trim_end().to_string())
+}
@@ -0,0 +1,84 @@
+//! Defaults read from ~/.config/create-desktop-file/config.toml, used for the details a flag,
+//! prompt or the form leaves out.
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use crate::desktop_entry::AppType;
+use crate::app_error::{AppError, ConfigError};
+use crate::user_details::Answers;
+use crate::{path, AppState};
+
+/// The config file inside $XDG_CONFIG_HOME (ex: ~/.config)
+pub const CONFIG_FILE: &str = "create-desktop-file/config.toml";
+
+/// The values of the config file, every key is optional
+/// (ex: categories = ["Development"], terminal = false, type = "Application", validate = true, scope = "local")
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub categories: Vec<String>,
+    pub terminal: Option<bool>,
+    #[serde(rename = "type")]
+    pub app_type: Option<AppType>,
+    /// Check every written entry with desktop-file-validate, as --validate does
+    pub validate: bool,
+    pub scope: Option<Scope>,
+}
+
+/// Where entries are installed when neither --local nor --global is given
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Local,
+    Global,
+}
+
+impl Config {
+    /// Reads the `config` file, or the `default_config` one (ex: [`default_path`]) when there's no
+    /// `config`. The default file doesn't have to exist
+    pub fn load(config: Option<&Path>, default_config: Option<&Path>) -> Result<Config, AppError> {
+        let path = match (config, default_config) {
+            (Some(config), _) => config.to_path_buf(),
+            (None, Some(default_config)) if default_config.is_file() => default_config.to_path_buf(),
+            _ => return Ok(Config::default()),
+        };
+        let toml = fs::read_to_string(&path)
+            .map_err(|e| AppError::Config { path: path.display().to_string(), error: ConfigError::Read(e) })?;
+        Config::parse(&toml, &path)
+    }
+
+    /// Parses the contents of the config file at `path`, an error names the file and the line
+    pub fn parse(toml: &str, path: &Path) -> Result<Config, AppError> {
+        toml::from_str(toml).map_err(|error| AppError::Config { path: path.display().to_string(), error: ConfigError::Parse(Box::new(error)) })
+    }
+
+    /// Fills in the details `state` was left without
+    pub fn fill_in(&self, state: &mut AppState) {
+        if state.categories.trim().is_empty() {
+            state.categories = self.categories.iter().map(|category| format!("{};", category)).collect();
+        }
+        if let Some(terminal) = self.terminal
+            && state.terminal_app.trim().is_empty() {
+            state.terminal_app = terminal.to_string();
+        }
+        if let Some(app_type) = self.app_type
+            && state.app_type.trim().is_empty() {
+            state.app_type = app_type.to_string();
+        }
+    }
+
+    /// The answers the --interactive prompts start out from
+    pub fn answers(&self) -> Answers {
+        Answers {
+            terminal: self.terminal.unwrap_or_default(),
+            app_type: self.app_type.unwrap_or_default(),
+            categories: self.categories.clone(),
+            ..Answers::default()
+        }
+    }
+}
+
+/// The user's config file, ex: ~/.config/create-desktop-file/config.toml
+pub fn default_path() -> Option<PathBuf> {
+    path::config_home().ok().map(|config_home| config_home.join(CONFIG_FILE))
+}
@@ -1,53 +1,1736 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::categories;
+use crate::error::{CreateDesktopError, ParseError};
+
+/// Version of the Desktop Entry Specification the generated files follow, written as the `Version` key
+pub const SPEC_VERSION: &str = "1.5";
+
+/// Start of the comment written above the entries this tool creates, see `generated_header`
+pub const HEADER_PREFIX: &str = "# Created by create-desktop-file";
+
+#[derive(Clone, Debug, Default)]
 pub struct DesktopEntry {
+    // Comment lines above the [Desktop Entry] group, kept as they were written
+    header_comments: Vec<String>,
     name: String,
+    generic_name: String,
     comment: String,
     exec_path: String,
+    try_exec: String,
     icon_path: String,
-    terminal_app: String,
-    app_type: String,
-    categories: String,
+    terminal: Option<bool>,
+    app_type: AppType,
+    categories: Vec<String>,
+    keywords: String,
+    mime_type: String,
+    startup_notify: Option<bool>,
+    startup_wm_class: String,
+    no_display: bool,
+    hidden: bool,
+    working_dir: String,
+    actions: Vec<DesktopAction>,
+    dbus_activatable: bool,
+    url: String,
+    only_show_in: String,
+    not_show_in: String,
+    prefers_non_default_gpu: bool,
+    spec_version: Option<String>,
+    // Translations keyed by locale (ex: fr, de_CH), written as Name[fr]= after the unlocalized key
+    localized_names: BTreeMap<String, String>,
+    localized_generic_names: BTreeMap<String, String>,
+    localized_comments: BTreeMap<String, String>,
+    // Keys and groups read from an existing file that the tool doesn't know about,
+    // kept exactly as they were written so editing a file doesn't lose them
+    extra_keys: Vec<(String, String)>,
+    extra_groups: Vec<(String, Vec<String>)>,
 }
 
 impl DesktopEntry {
+    /// Starts a new entry, ex: `DesktopEntry::builder().name("Foo").exec("/usr/bin/foo").build()`
+    pub fn builder() -> DesktopEntryBuilder {
+        DesktopEntryBuilder::new()
+    }
+
+    /// Kept for backwards compatibility, prefer `DesktopEntry::builder`. Fails with
+    /// [`CreateDesktopError::InvalidField`] when `terminal_app` isn't true, false or empty
     pub fn new(
         name: String,
         comment: String,
         exec_path: String,
         icon_path: String,
         terminal_app: String,
-        app_type: String,
+        app_type: AppType,
         categories: String,
-
-    ) -> Self {
-        DesktopEntry {
+    ) -> Result<Self, CreateDesktopError> {
+        Ok(DesktopEntry {
+            header_comments: Vec::new(),
             name: name.to_string(),
+            generic_name: String::new(),
             comment: comment.to_string(),
             exec_path: exec_path.to_string(),
+            try_exec: String::new(),
             icon_path: icon_path.to_string(),
-            terminal_app: terminal_app.to_string(),
-            app_type: app_type.to_string(),
-            categories: categories.to_string(),
-        }
-    }
-
-    pub fn to_string(&self) -> String {
-        format!(
-            "[Desktop Entry]\n\
-            Name={}\n\
-            Comment={}\n\
-            Exec={}\n\
-            Icon={}\n\
-            Terminal={}\n\
-            Type={}\n\
-            Categories={}",
-
-            self.name.trim(),
-            self.comment.trim(),
-            self.exec_path.trim(),
-            self.icon_path.trim(),
-            self.terminal_app.trim(),
-            self.app_type.trim(),
-            self.categories.trim(),
-        )
-    }
-}
\ No newline at end of file
+            terminal: parse_terminal(&terminal_app)?,
+            app_type,
+            categories: categories::parse_categories(&categories),
+            keywords: String::new(),
+            mime_type: String::new(),
+            startup_notify: None,
+            startup_wm_class: String::new(),
+            no_display: false,
+            hidden: false,
+            working_dir: String::new(),
+            actions: Vec::new(),
+            dbus_activatable: false,
+            url: String::new(),
+            only_show_in: String::new(),
+            not_show_in: String::new(),
+            prefers_non_default_gpu: false,
+            spec_version: Some(SPEC_VERSION.to_string()),
+            localized_names: BTreeMap::new(),
+            localized_generic_names: BTreeMap::new(),
+            localized_comments: BTreeMap::new(),
+            extra_keys: Vec::new(),
+            extra_groups: Vec::new(),
+        })
+    }
+
+    /// Returns what's wrong with the Categories, warned about normally and refused with --strict
+    pub fn category_problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Err(unknown) = categories::validate_categories(&self.categories) {
+            problems.push(format!(
+                "Unknown categories: {} (see https://specifications.freedesktop.org/menu-spec/latest/category-registry.html)",
+                unknown.join(", ")
+            ));
+        }
+        if !self.categories.is_empty() && !categories::has_main_category(&self.categories) {
+            problems.push(format!(
+                "Categories has no main category ({}), menus may not show the entry",
+                categories::MAIN_CATEGORIES.join(", ")
+            ));
+        }
+        problems
+    }
+
+    /// Checks the entry against the spec before it's written. Errors make an invalid file,
+    /// warnings are worth telling the user about but the file still works
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        if self.name.trim().is_empty() {
+            issues.push(ValidationIssue::error("Name", "Name is required"));
+        }
+        let needs_exec = self.app_type == AppType::Application && !self.hidden && !self.dbus_activatable;
+        if needs_exec && self.exec_path.trim().is_empty() {
+            issues.push(ValidationIssue::error("Exec", "Exec is required for Type=Application entries, use --exec-path"));
+        }
+        if self.app_type == AppType::Link && self.url.trim().is_empty() {
+            issues.push(ValidationIssue::error("URL", "URL is required for Type=Link entries, use --url"));
+        }
+
+        if self.no_display && !self.categories.is_empty() {
+            issues.push(ValidationIssue::warning("NoDisplay", "NoDisplay=true hides the entry from menus, so Categories will have no effect"));
+        }
+        let try_exec = self.try_exec.trim();
+        if !try_exec.is_empty() && !is_executable_program(try_exec) {
+            issues.push(ValidationIssue::warning("TryExec", format!("TryExec {} is not an executable file, launchers will hide the entry", try_exec)));
+        }
+        if check_icon(&self.icon_path) == IconCheck::Missing {
+            issues.push(ValidationIssue::warning("Icon", format!("Icon {} does not exist", self.icon_path.trim())));
+        }
+        if !self.working_dir.is_empty() && !Path::new(&self.working_dir).is_dir() {
+            issues.push(ValidationIssue::warning("Path", format!("Working directory {} does not exist", self.working_dir)));
+        }
+        for problem in self.category_problems() {
+            issues.push(ValidationIssue::warning("Categories", problem));
+        }
+        for (key, environments) in [("OnlyShowIn", &self.only_show_in), ("NotShowIn", &self.not_show_in)] {
+            let unknown = categories::unknown_environments(environments);
+            if !unknown.is_empty() {
+                issues.push(ValidationIssue::warning(key, format!("{} has unregistered desktop environments: {}", key, unknown.join(", "))));
+            }
+        }
+        // The CLI refuses these, but they can still come from the builder or a file being edited
+        let malformed: Vec<&str> = self.mime_type.split(';')
+            .map(str::trim)
+            .filter(|mime_type| !mime_type.is_empty() && !is_valid_mime_type(mime_type))
+            .collect();
+        if !malformed.is_empty() {
+            issues.push(ValidationIssue::warning("MimeType", format!("MimeType has entries that aren't type/subtype: {}", malformed.join(", "))));
+        }
+        let reserved = unquoted_reserved_characters(&self.exec_path);
+        if !reserved.is_empty() {
+            issues.push(ValidationIssue::warning("Exec", format!(
+                "Exec has {} outside of double quotes, the spec reserves them so launchers may not run it as intended",
+                reserved.iter().map(|c| format!("{:?}", c)).collect::<Vec<_>>().join(", ")
+            )));
+        }
+        issues
+    }
+
+    /// Writes the entry like `to_string`, except that a key whose value is the same as in `original`
+    /// (the file the entry was loaded from) keeps the line exactly as the file had it, escapes and all
+    pub fn to_string_keeping(&self, original: &str) -> String {
+        let Ok(loaded) = DesktopEntry::from_str(original) else {
+            return self.to_string();
+        };
+        let original_lines = key_lines(original);
+        let loaded_lines = key_lines(&loaded.to_string());
+
+        let mut group = String::new();
+        let mut contents = String::new();
+        for line in self.to_string().lines() {
+            if line.starts_with('[') {
+                group = line.to_string();
+            }
+            let kept = line.split_once('=')
+                .map(|(key, _)| (group.clone(), key.to_string()))
+                .filter(|group_key| loaded_lines.get(group_key).map(String::as_str) == Some(line))
+                .and_then(|group_key| original_lines.get(&group_key));
+            contents.push_str(kept.map(String::as_str).unwrap_or(line));
+            contents.push('\n');
+        }
+        contents
+    }
+
+    /// Lists the keys that differ between `self` and `other`, in the order they're written.
+    /// Keys of other groups are prefixed with the group (ex: `[Desktop Action new-window] Exec`).
+    pub fn diff(&self, other: &DesktopEntry) -> Vec<FieldChange> {
+        let old = self.key_values();
+        let new = other.key_values();
+        let find = |pairs: &[(String, String)], key: &str| {
+            pairs.iter().find(|(pair_key, _)| pair_key == key).map(|(_, value)| value.clone())
+        };
+
+        let mut changes: Vec<FieldChange> = old.iter()
+            .map(|(key, value)| FieldChange { key: key.clone(), old: Some(value.clone()), new: find(&new, key) })
+            .filter(|change| change.old != change.new)
+            .collect();
+        changes.extend(new.iter()
+            .filter(|(key, _)| find(&old, key).is_none())
+            .map(|(key, value)| FieldChange { key: key.clone(), old: None, new: Some(value.clone()) }));
+        changes
+    }
+
+    /// The keys with their unescaped values as they're written, keys of other groups prefixed with
+    /// the group like in [`DesktopEntry::diff`]. Entries are compared through these the way a launcher would see them,
+    /// so comments (ex: the header above the first group) aren't keys even when they contain a `=`
+    pub fn key_values(&self) -> Vec<(String, String)> {
+        let mut group = None;
+        let mut pairs = Vec::new();
+        for line in self.to_string().lines() {
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                group = Some(name.to_string());
+            } else if !line.trim_start().starts_with('#')
+                && let Some(group) = &group
+                && let Some((key, value)) = line.split_once('=') {
+                let key = if group == "Desktop Entry" { key.to_string() } else { format!("[{}] {}", group, key) };
+                pairs.push((key, unescape_value(value)));
+            }
+        }
+        pairs
+    }
+
+    /// Puts the `generated_header` comment above the entry, replacing the one from an earlier run
+    pub fn with_generated_header(mut self) -> Self {
+        self.header_comments.retain(|line| !line.starts_with(HEADER_PREFIX));
+        self.header_comments.insert(0, generated_header());
+        self
+    }
+
+    /// Returns non-fatal problems with the entry, worth telling the user about before writing it
+    pub fn warnings(&self) -> Vec<String> {
+        self.validate()
+            .into_iter()
+            .filter(|issue| issue.severity == Severity::Warning)
+            .map(|issue| issue.message)
+            .collect()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The file name the entry is written to by default, its Name slugified (ex: my-cool-app.desktop)
+    pub fn file_name(&self) -> String {
+        format!("{}.desktop", slugify(&self.name))
+    }
+
+    pub fn is_dbus_activatable(&self) -> bool {
+        self.dbus_activatable
+    }
+
+    /// Sets the GenericName key (e.g. "Text Editor"), only written when non-empty
+    pub fn with_generic_name(mut self, generic_name: String) -> Self {
+        self.generic_name = generic_name;
+        self
+    }
+
+    /// Sets the semicolon-separated Keywords key used by launchers for search
+    pub fn with_keywords(mut self, keywords: String) -> Self {
+        self.keywords = keywords;
+        self
+    }
+
+    /// Sets the semicolon-separated MimeType key listing the file types the application handles
+    pub fn with_mime_type(mut self, mime_type: String) -> Self {
+        self.mime_type = mime_type;
+        self
+    }
+
+    /// Sets the StartupNotify key, only written when explicitly provided
+    pub fn with_startup_notify(mut self, startup_notify: Option<bool>) -> Self {
+        self.startup_notify = startup_notify;
+        self
+    }
+
+    /// Sets the StartupWMClass key so the taskbar can match the launched window to this entry
+    pub fn with_startup_wm_class(mut self, startup_wm_class: String) -> Self {
+        self.startup_wm_class = startup_wm_class;
+        self
+    }
+}
+
+/// What `DesktopEntryBuilder::build` fails with, the same errors the rest of the tool reports
+pub type BuildError = CreateDesktopError;
+
+/// Chainable alternative to `DesktopEntry::new`, so fields can't be passed in the wrong order
+pub struct DesktopEntryBuilder {
+    entry: DesktopEntry,
+    field_code: Option<String>,
+    // Checked when building, so a bad or repeated key is reported rather than written
+    extension_keys: Vec<(String, String)>,
+}
+
+/// Starts from an existing entry, so only the fields that are set change
+impl From<DesktopEntry> for DesktopEntryBuilder {
+    fn from(entry: DesktopEntry) -> Self {
+        DesktopEntryBuilder { entry, field_code: None, extension_keys: Vec::new() }
+    }
+}
+
+impl Default for DesktopEntryBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DesktopEntryBuilder {
+    pub fn new() -> Self {
+        DesktopEntryBuilder {
+            entry: DesktopEntry {
+                spec_version: Some(SPEC_VERSION.to_string()),
+                ..DesktopEntry::default()
+            },
+            field_code: None,
+            extension_keys: Vec::new(),
+        }
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.entry.name = name.into();
+        self
+    }
+
+    pub fn generic_name(mut self, generic_name: impl Into<String>) -> Self {
+        self.entry = self.entry.with_generic_name(generic_name.into());
+        self
+    }
+
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.entry.comment = comment.into();
+        self
+    }
+
+    pub fn exec(mut self, exec_path: impl Into<String>) -> Self {
+        // Only what's given here is quoted, an Exec read from a file is kept as it was written
+        self.entry.exec_path = quote_exec(&exec_path.into());
+        self
+    }
+
+    /// Appends a field code to Exec when building (f, F, u or U), so the application can be
+    /// given files or URLs to open, ex: through "Open With" in a file manager
+    pub fn field_code(mut self, field_code: impl Into<String>) -> Self {
+        self.field_code = Some(field_code.into());
+        self
+    }
+
+    pub fn icon(mut self, icon_path: impl Into<String>) -> Self {
+        self.entry.icon_path = icon_path.into();
+        self
+    }
+
+    /// Sets whether the application runs in a terminal, written as Terminal=true / false
+    pub fn terminal(mut self, terminal: bool) -> Self {
+        self.entry.terminal = Some(terminal);
+        self
+    }
+
+    pub fn app_type(mut self, app_type: AppType) -> Self {
+        self.entry.app_type = app_type;
+        self
+    }
+
+    /// Sets the Categories, written in the given order with a `;` after each (ex: Development;IDE;)
+    pub fn categories<S: Into<String>>(mut self, categories: impl IntoIterator<Item = S>) -> Self {
+        self.entry.categories = categories.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn keywords(mut self, keywords: impl Into<String>) -> Self {
+        self.entry = self.entry.with_keywords(keywords.into());
+        self
+    }
+
+    pub fn mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.entry = self.entry.with_mime_type(mime_type.into());
+        self
+    }
+
+    pub fn startup_notify(mut self, startup_notify: Option<bool>) -> Self {
+        self.entry = self.entry.with_startup_notify(startup_notify);
+        self
+    }
+
+    pub fn startup_wm_class(mut self, startup_wm_class: impl Into<String>) -> Self {
+        self.entry = self.entry.with_startup_wm_class(startup_wm_class.into());
+        self
+    }
+
+    pub fn no_display(mut self, no_display: bool) -> Self {
+        self.entry.no_display = no_display;
+        self
+    }
+
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.entry.hidden = hidden;
+        self
+    }
+
+    /// Sets the TryExec key, a program that has to be installed for launchers to show the entry
+    pub fn try_exec(mut self, try_exec: impl Into<String>) -> Self {
+        self.entry.try_exec = try_exec.into();
+        self
+    }
+
+    /// Sets the Path key, the working directory the application is started in
+    pub fn working_dir(mut self, working_dir: impl Into<String>) -> Self {
+        let working_dir = working_dir.into();
+        let working_dir = working_dir.trim();
+        self.entry.working_dir = match working_dir.trim_end_matches('/') {
+            "" if working_dir.starts_with('/') => String::from("/"),
+            trimmed => trimmed.to_string(),
+        };
+        self
+    }
+
+    /// Adds a `[Desktop Action]` group, shown as an extra item in the launcher's right-click menu
+    pub fn action(mut self, action: DesktopAction) -> Self {
+        self.entry.actions.push(action);
+        self
+    }
+
+    /// Sets DBusActivatable, for applications launched through D-Bus activation
+    pub fn dbus_activatable(mut self, dbus_activatable: bool) -> Self {
+        self.entry.dbus_activatable = dbus_activatable;
+        self
+    }
+
+    /// Sets the URL a Type=Link entry points to
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.entry.url = url.into();
+        self
+    }
+
+    /// Sets the desktop environments the entry is only shown in (ex: KDE;XFCE;)
+    pub fn only_show_in(mut self, only_show_in: impl Into<String>) -> Self {
+        self.entry.only_show_in = only_show_in.into();
+        self
+    }
+
+    /// Sets the desktop environments the entry is hidden in (ex: GNOME;)
+    pub fn not_show_in(mut self, not_show_in: impl Into<String>) -> Self {
+        self.entry.not_show_in = not_show_in.into();
+        self
+    }
+
+    /// Sets PrefersNonDefaultGPU, so desktops offer to launch it on the dedicated graphics card
+    pub fn prefers_non_default_gpu(mut self, prefers_non_default_gpu: bool) -> Self {
+        self.entry.prefers_non_default_gpu = prefers_non_default_gpu;
+        self
+    }
+
+    /// Overrides the spec version written as the `Version` key, `SPEC_VERSION` by default
+    pub fn spec_version(mut self, spec_version: impl Into<String>) -> Self {
+        self.entry.spec_version = Some(spec_version.into());
+        self
+    }
+
+    /// Leaves the `Version` key out of the entry
+    pub fn without_spec_version(mut self) -> Self {
+        self.entry.spec_version = None;
+        self
+    }
+
+    /// Adds a translated Name (written as `Name[locale]=`)
+    pub fn localized_name(mut self, locale: impl Into<String>, name: impl Into<String>) -> Self {
+        self.entry.localized_names.insert(locale.into(), name.into());
+        self
+    }
+
+    /// Adds a translated GenericName (written as `GenericName[locale]=`)
+    pub fn localized_generic_name(mut self, locale: impl Into<String>, generic_name: impl Into<String>) -> Self {
+        self.entry.localized_generic_names.insert(locale.into(), generic_name.into());
+        self
+    }
+
+    /// Adds a translated Comment (written as `Comment[locale]=`)
+    pub fn localized_comment(mut self, locale: impl Into<String>, comment: impl Into<String>) -> Self {
+        self.entry.localized_comments.insert(locale.into(), comment.into());
+        self
+    }
+
+    /// Adds a vendor extension key (ex: `X-GNOME-UsesNotifications`), written after the standard keys.
+    /// An extension key already in an edited entry gets the new value
+    pub fn extension_key(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.extension_keys.push((key.into(), value.into()));
+        self
+    }
+
+    /// Returns the entry, or an error if no name was given (or no URL for a Type=Link entry, or a URL for any other type)
+    pub fn build(mut self) -> Result<DesktopEntry, BuildError> {
+        if self.entry.name.trim().is_empty() {
+            return Err(CreateDesktopError::MissingName);
+        }
+        if self.entry.app_type == AppType::Link && self.entry.url.trim().is_empty() {
+            return Err(CreateDesktopError::MissingUrl);
+        }
+        if self.entry.app_type != AppType::Link && !self.entry.url.trim().is_empty() {
+            return Err(CreateDesktopError::UnexpectedUrl(self.entry.app_type));
+        }
+        // The spec allows only one of the two, a list of environments to show in or to hide from
+        if !self.entry.only_show_in.trim().is_empty() && !self.entry.not_show_in.trim().is_empty() {
+            return Err(CreateDesktopError::ConflictingKeys {
+                first: String::from("OnlyShowIn"),
+                second: String::from("NotShowIn"),
+            });
+        }
+        if let Some(field_code) = self.field_code.as_deref().map(str::trim).filter(|code| !code.is_empty()) {
+            if !matches!(field_code, "f" | "F" | "u" | "U") {
+                return Err(CreateDesktopError::invalid_field("field code", field_code));
+            }
+            let code = format!("%{}", field_code);
+            if !self.entry.exec_path.split_whitespace().any(|arg| arg == code) {
+                self.entry.exec_path = format!("{} {}", self.entry.exec_path.trim(), code);
+            }
+        }
+        validate_exec(&self.entry.exec_path)?;
+        split_exec(&self.entry.exec_path)?;
+        let locales = self.entry.localized_names.keys()
+            .chain(self.entry.localized_generic_names.keys())
+            .chain(self.entry.localized_comments.keys());
+        for locale in locales {
+            if !is_valid_locale(locale) {
+                return Err(CreateDesktopError::invalid_field("locale", locale));
+            }
+        }
+        // The id is written into the group header, so it can't carry a newline or a bracket
+        if let Some(action) = self.entry.actions.iter().find(|action| !is_action_id(&action.id)) {
+            return Err(CreateDesktopError::invalid_field("action id", &action.id));
+        }
+        // The other keys of an action are written as they are, so each has to be a key that can't
+        // run into the next line or be read back as another key
+        for action in &self.entry.actions {
+            let mut keys = vec!["Name", "Exec", "Icon"];
+            for (key, _) in &action.extra_keys {
+                if !is_key(key) {
+                    return Err(CreateDesktopError::invalid_field("action key", key));
+                }
+                if keys.contains(&key.as_str()) {
+                    return Err(CreateDesktopError::DuplicateKey(key.clone()));
+                }
+                keys.push(key);
+            }
+        }
+        let mut added: Vec<String> = Vec::new();
+        for (key, value) in self.extension_keys {
+            let key = key.trim().to_string();
+            if !is_extension_key(&key) {
+                return Err(CreateDesktopError::invalid_field("extension key", &key));
+            }
+            if added.contains(&key) {
+                return Err(CreateDesktopError::DuplicateKey(key));
+            }
+            let value = escape_value(value.trim());
+            match self.entry.extra_keys.iter_mut().find(|(extra_key, _)| *extra_key == key) {
+                Some((_, existing)) => *existing = value,
+                None => self.entry.extra_keys.push((key.clone(), value)),
+            }
+            added.push(key);
+        }
+        Ok(self.entry)
+    }
+}
+
+/// The `Type` of an entry, one of the three types the spec defines
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AppType {
+    #[default]
+    Application,
+    Link,
+    Directory,
+}
+
+impl AppType {
+    pub const ALL: [AppType; 3] = [AppType::Application, AppType::Link, AppType::Directory];
+}
+
+impl fmt::Display for AppType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let app_type = match self {
+            AppType::Application => "Application",
+            AppType::Link => "Link",
+            AppType::Directory => "Directory",
+        };
+        write!(f, "{}", app_type)
+    }
+}
+
+impl FromStr for AppType {
+    type Err = CreateDesktopError;
+
+    /// Matches the type case-insensitively (`link` is `Link`), an empty value is an Application
+    fn from_str(app_type: &str) -> Result<Self, Self::Err> {
+        let app_type = app_type.trim();
+        if app_type.is_empty() {
+            return Ok(AppType::Application);
+        }
+        AppType::ALL.into_iter()
+            .find(|known| known.to_string().eq_ignore_ascii_case(app_type))
+            .ok_or_else(|| CreateDesktopError::UnknownType(app_type.to_string()))
+    }
+}
+
+/// How bad a `ValidationIssue` is, errors stop the entry from being written
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem `DesktopEntry::validate` found, with the key it's about
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub key: String,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn error(key: &str, message: impl Into<String>) -> Self {
+        ValidationIssue { severity: Severity::Error, key: key.to_string(), message: message.into() }
+    }
+
+    fn warning(key: &str, message: impl Into<String>) -> Self {
+        ValidationIssue { severity: Severity::Warning, key: key.to_string(), message: message.into() }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Entries are equal when they'd be written with the same keys and values, so surrounding
+/// spaces that aren't written and the comments above the entry don't count
+impl PartialEq for DesktopEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.key_values() == other.key_values()
+    }
+}
+
+/// A key that differs between two entries, see `DesktopEntry::diff`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    pub key: String,
+    /// None when the key is only in the new entry
+    pub old: Option<String>,
+    /// None when the key is only in the old entry
+    pub new: Option<String>,
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, "{} changed from {:?} to {:?}", self.key, old, new),
+            (None, Some(new)) => write!(f, "{} added as {:?}", self.key, new),
+            (Some(old), None) => write!(f, "{} removed (was {:?})", self.key, old),
+            (None, None) => write!(f, "{} unchanged", self.key),
+        }
+    }
+}
+
+/// An additional application action, written as its own `[Desktop Action <id>]` group
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DesktopAction {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub exec: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub icon: String,
+    // Other keys read from an existing file (ex: Name[fr]), kept escaped as they were written
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra_keys: Vec<(String, String)>,
+}
+
+impl DesktopAction {
+    /// Parses the `--action` syntax, `id:Name=New Window:Exec=/usr/bin/foo --new-window`,
+    /// or the shorter `id:New Window:/usr/bin/foo --new-window` when only Name and Exec are needed.
+    /// A `:` that isn't followed by a known key is part of the value, so Exec can contain URLs.
+    pub fn parse(spec: &str) -> Result<DesktopAction, CreateDesktopError> {
+        let invalid = || CreateDesktopError::invalid_field("Action", spec);
+
+        let mut segments = spec.trim().split(':');
+        let id = segments.next().unwrap_or_default().trim().to_string();
+
+        let is_shorthand = spec.trim().split(':').nth(1)
+            .is_some_and(|segment| !matches!(segment.split_once('='), Some(("Name" | "Exec" | "Icon", _))));
+        if is_shorthand {
+            let name = segments.next().unwrap_or_default().to_string();
+            let exec = segments.collect::<Vec<_>>().join(":");
+            // Without a key to say what's what, both the name and the command have to be there
+            if exec.trim().is_empty() {
+                return Err(invalid());
+            }
+            return DesktopAction::validated(DesktopAction { id, name, exec, ..DesktopAction::default() }, invalid);
+        }
+
+        let mut fields: Vec<(&str, String)> = Vec::new();
+        for segment in segments {
+            match segment.split_once('=') {
+                Some((key @ ("Name" | "Exec" | "Icon"), value)) => fields.push((key, value.to_string())),
+                _ => match fields.last_mut() {
+                    Some((_, value)) => {
+                        value.push(':');
+                        value.push_str(segment);
+                    }
+                    None => return Err(invalid()),
+                },
+            }
+        }
+
+        let mut action = DesktopAction { id, ..DesktopAction::default() };
+        for (key, value) in fields {
+            match key {
+                "Name" => action.name = value,
+                "Exec" => action.exec = value,
+                _ => action.icon = value,
+            }
+        }
+
+        DesktopAction::validated(action, invalid)
+    }
+
+    fn validated(action: DesktopAction, invalid: impl Fn() -> CreateDesktopError) -> Result<DesktopAction, CreateDesktopError> {
+        if !is_action_id(&action.id) || action.name.trim().is_empty() {
+            return Err(invalid());
+        }
+        Ok(action)
+    }
+}
+
+impl DesktopEntry {
+    /// The keys of the [Desktop Entry] group with their escaped values, in the canonical order:
+    /// Type, Version, Name, GenericName, Comment (each followed by its translations), Icon,
+    /// Exec (URL for links), TryExec, Path, Terminal, Categories, Keywords, MimeType, OnlyShowIn,
+    /// NotShowIn, StartupNotify, StartupWMClass, NoDisplay, Hidden, DBusActivatable,
+    /// PrefersNonDefaultGPU, Actions and last the extension and unknown keys as they were given.
+    /// Name and Type are required by the spec, the remaining keys are only written when set.
+    fn entry_pairs(&self) -> Vec<(String, String)> {
+        let app_type = self.app_type;
+        let mut pairs = vec![(String::from("Type"), app_type.to_string())];
+        if let Some(spec_version) = &self.spec_version {
+            push_optional(&mut pairs, "Version", spec_version);
+        }
+        pairs.push((String::from("Name"), escape_value(&self.name)));
+        push_localized(&mut pairs, "Name", &self.localized_names);
+        push_optional_text(&mut pairs, "GenericName", &self.generic_name);
+        push_localized(&mut pairs, "GenericName", &self.localized_generic_names);
+        push_optional_text(&mut pairs, "Comment", &self.comment);
+        push_localized(&mut pairs, "Comment", &self.localized_comments);
+        push_optional(&mut pairs, "Icon", &self.icon_path);
+        // Hidden entries only mask another entry and D-Bus activated ones are launched by
+        // their bus name, so neither needs an Exec
+        if app_type == AppType::Application && !self.hidden && !self.dbus_activatable {
+            pairs.push((String::from("Exec"), escape_value(self.exec_path.trim())));
+        } else if app_type == AppType::Link {
+            // Links open their URL instead of running anything
+            pairs.push((String::from("URL"), escape_value(self.url.trim())));
+        } else {
+            push_optional(&mut pairs, "Exec", &self.exec_path);
+        }
+        push_optional(&mut pairs, "TryExec", &self.try_exec);
+        push_optional(&mut pairs, "Path", &self.working_dir);
+        if let Some(terminal) = self.terminal {
+            pairs.push((String::from("Terminal"), terminal.to_string()));
+        }
+        push_optional_list(&mut pairs, "Categories", &join_list(&self.categories));
+        for (key, value) in [
+            ("Keywords", &self.keywords),
+            ("MimeType", &self.mime_type),
+            ("OnlyShowIn", &self.only_show_in),
+            ("NotShowIn", &self.not_show_in),
+        ] {
+            if !value.trim().is_empty() {
+                push_optional_list(&mut pairs, key, &ensure_trailing_semicolon(value));
+            }
+        }
+        if let Some(startup_notify) = self.startup_notify {
+            pairs.push((String::from("StartupNotify"), startup_notify.to_string()));
+        }
+        push_optional(&mut pairs, "StartupWMClass", &self.startup_wm_class);
+        for (key, enabled) in [
+            ("NoDisplay", self.no_display),
+            ("Hidden", self.hidden),
+            ("DBusActivatable", self.dbus_activatable),
+            ("PrefersNonDefaultGPU", self.prefers_non_default_gpu),
+        ] {
+            if enabled {
+                pairs.push((key.to_string(), String::from("true")));
+            }
+        }
+        if !self.actions.is_empty() {
+            let ids: Vec<&str> = self.actions.iter().map(|action| action.id.as_str()).collect();
+            pairs.push((String::from("Actions"), format!("{};", ids.join(";"))));
+        }
+        for (key, value) in &self.extra_keys {
+            pairs.push((single_line(key), single_line(value)));
+        }
+        pairs
+    }
+}
+
+/// Writes the entry as a .desktop file, one `Key=value` per line and ending with a newline.
+/// Parsing a file written this way and writing it again gives back the same bytes.
+impl fmt::Display for DesktopEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = self.header_comments.clone();
+        lines.push(String::from("[Desktop Entry]"));
+        lines.extend(self.entry_pairs().into_iter().map(|(key, value)| format!("{}={}", key, value)));
+
+        for action in &self.actions {
+            let mut pairs = vec![(String::from("Name"), escape_value(action.name.trim()))];
+            push_optional(&mut pairs, "Exec", &action.exec);
+            push_optional(&mut pairs, "Icon", &action.icon);
+            for (key, value) in &action.extra_keys {
+                pairs.push((single_line(key), single_line(value)));
+            }
+            lines.push(String::new());
+            lines.push(format!("[Desktop Action {}]", action.id));
+            lines.extend(pairs.into_iter().map(|(key, value)| format!("{}={}", key, value)));
+        }
+
+        for (name, group_lines) in &self.extra_groups {
+            lines.push(String::new());
+            lines.push(format!("[{}]", name));
+            lines.extend(group_lines.iter().cloned());
+        }
+
+        for line in lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DesktopEntry {
+    type Err = ParseError;
+
+    /// Reads an entry back from the contents of a .desktop file, the inverse of `to_string`.
+    /// Comments and blank lines are skipped, unknown keys and groups are kept as they are.
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let mut entry = DesktopEntry::default();
+        let mut group: Option<String> = None;
+        let mut has_entry_group = false;
+        // The spec doesn't allow a key twice in a group, or a group twice in a file
+        let mut seen_groups: Vec<String> = Vec::new();
+        let mut seen_keys: Vec<String> = Vec::new();
+
+        for (index, line) in contents.lines().enumerate() {
+            let line_number = Some(index + 1);
+            let line = line.trim();
+            if line.starts_with('#') && group.is_none() {
+                entry.header_comments.push(line.to_string());
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                if seen_groups.iter().any(|seen| seen == name) {
+                    return Err(ParseError::new(line_number, format!("duplicate group [{}]", name)));
+                }
+                seen_groups.push(name.to_string());
+                seen_keys.clear();
+                if name == "Desktop Entry" {
+                    has_entry_group = true;
+                } else if let Some(id) = name.strip_prefix("Desktop Action ") {
+                    entry.actions.push(DesktopAction { id: id.to_string(), ..DesktopAction::default() });
+                } else {
+                    entry.extra_groups.push((name.to_string(), Vec::new()));
+                }
+                group = Some(name.to_string());
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(ParseError::new(line_number, format!("expected Key=Value, found {:?}", line)));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            if group.is_some() {
+                if seen_keys.iter().any(|seen| seen == key) {
+                    return Err(ParseError::new(line_number, format!("duplicate key {}", key)));
+                }
+                seen_keys.push(key.to_string());
+            }
+
+            match group.as_deref() {
+                Some("Desktop Entry") => entry.set_key(key, value, line_number)?,
+                Some(name) if name.starts_with("Desktop Action ") => {
+                    if let Some(action) = entry.actions.last_mut() {
+                        match key {
+                            "Name" => action.name = unescape_value(value),
+                            "Exec" => action.exec = unescape_value(value),
+                            "Icon" => action.icon = unescape_value(value),
+                            _ => action.extra_keys.push((key.to_string(), value.to_string())),
+                        }
+                    }
+                }
+                Some(_) => {
+                    if let Some((_, group_lines)) = entry.extra_groups.last_mut() {
+                        group_lines.push(line.to_string());
+                    }
+                }
+                None => return Err(ParseError::new(line_number, format!("{} is outside of a group", key))),
+            }
+        }
+
+        if !has_entry_group {
+            return Err(ParseError::new(None, "missing [Desktop Entry] group"));
+        }
+        if entry.name.trim().is_empty() {
+            return Err(ParseError::new(None, "missing Name key"));
+        }
+        Ok(entry)
+    }
+}
+
+impl DesktopEntry {
+    /// Sets the field a `[Desktop Entry]` key maps to from its escaped value,
+    /// keeping keys the tool doesn't know about as they were written
+    fn set_key(&mut self, key: &str, raw_value: &str, line: Option<usize>) -> Result<(), ParseError> {
+        let value = unescape_value(raw_value);
+        let boolean = |value: &str| match value {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(ParseError::new(line, format!("expected true or false for {}, found {:?}", key, value))),
+        };
+
+        if let Some((key, locale)) = key.strip_suffix(']').and_then(|key| key.split_once('[')) {
+            let translations = match key {
+                "Name" => &mut self.localized_names,
+                "GenericName" => &mut self.localized_generic_names,
+                "Comment" => &mut self.localized_comments,
+                _ => {
+                    self.extra_keys.push((format!("{}[{}]", key, locale), raw_value.to_string()));
+                    return Ok(());
+                }
+            };
+            translations.insert(locale.to_string(), value);
+            return Ok(());
+        }
+
+        match key {
+            "Version" => self.spec_version = Some(value),
+            "Name" => self.name = value,
+            "GenericName" => self.generic_name = value,
+            "Comment" => self.comment = value,
+            "Exec" => self.exec_path = value,
+            "TryExec" => self.try_exec = value,
+            "URL" => self.url = value,
+            "Icon" => self.icon_path = value,
+            "Path" => self.working_dir = value,
+            "Terminal" => self.terminal = Some(boolean(&value)?),
+            "Type" => self.app_type = AppType::from_str(&value).map_err(|e| ParseError::new(line, e.to_string()))?,
+            "Categories" => self.categories = categories::parse_categories(&value),
+            "OnlyShowIn" => self.only_show_in = value,
+            "NotShowIn" => self.not_show_in = value,
+            "MimeType" => self.mime_type = value,
+            "StartupNotify" => self.startup_notify = Some(boolean(&value)?),
+            "StartupWMClass" => self.startup_wm_class = value,
+            "Keywords" => self.keywords = value,
+            "NoDisplay" => self.no_display = boolean(&value)?,
+            "Hidden" => self.hidden = boolean(&value)?,
+            "DBusActivatable" => self.dbus_activatable = boolean(&value)?,
+            "PrefersNonDefaultGPU" => self.prefers_non_default_gpu = boolean(&value)?,
+            // Written from the action list
+            "Actions" => {}
+            _ => self.extra_keys.push((key.to_string(), raw_value.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Returns the unescaped value `key` would be written with, or None when it's left out
+    pub fn value(&self, key: &str) -> Option<String> {
+        let flag = |set: bool| set.then(|| String::from("true"));
+
+        let value = if let Some((base, locale)) = key.strip_suffix(']').and_then(|key| key.split_once('[')) {
+            match base {
+                "Name" => self.localized_names.get(locale).cloned(),
+                "GenericName" => self.localized_generic_names.get(locale).cloned(),
+                "Comment" => self.localized_comments.get(locale).cloned(),
+                _ => None,
+            }
+        } else {
+            match key {
+                "Version" => self.spec_version.clone(),
+                "Name" => Some(self.name.clone()),
+                "GenericName" => Some(self.generic_name.clone()),
+                "Comment" => Some(self.comment.clone()),
+                "Exec" => Some(self.exec_path.clone()),
+                "TryExec" => Some(self.try_exec.clone()),
+                "URL" => Some(self.url.clone()),
+                "Icon" => Some(self.icon_path.clone()),
+                "Path" => Some(self.working_dir.clone()),
+                "Terminal" => self.terminal.map(|terminal| terminal.to_string()),
+                "Type" => Some(self.app_type.to_string()),
+                "Categories" => Some(join_list(&self.categories)),
+                "OnlyShowIn" => Some(self.only_show_in.clone()),
+                "NotShowIn" => Some(self.not_show_in.clone()),
+                "MimeType" => Some(self.mime_type.clone()),
+                "StartupNotify" => self.startup_notify.map(|startup_notify| startup_notify.to_string()),
+                "StartupWMClass" => Some(self.startup_wm_class.clone()),
+                "Keywords" => Some(self.keywords.clone()),
+                "NoDisplay" => flag(self.no_display),
+                "Hidden" => flag(self.hidden),
+                "DBusActivatable" => flag(self.dbus_activatable),
+                "PrefersNonDefaultGPU" => flag(self.prefers_non_default_gpu),
+                _ => None,
+            }
+        };
+
+        value
+            .or_else(|| self.extra_keys.iter().find(|(extra_key, _)| extra_key == key).map(|(_, value)| unescape_value(value)))
+            .filter(|value| !value.trim().is_empty())
+    }
+}
+
+/// Adds `key[locale]=value` for each translation, skipping empty ones
+fn push_localized(pairs: &mut Vec<(String, String)>, key: &str, translations: &BTreeMap<String, String>) {
+    for (locale, value) in translations {
+        push_optional_text(pairs, &format!("{}[{}]", key, locale), value);
+    }
+}
+
+/// Adds `key=value` to the output only when the trimmed value is non-empty
+fn push_optional(pairs: &mut Vec<(String, String)>, key: &str, value: &str) {
+    let value = value.trim();
+    if !value.is_empty() {
+        pairs.push((key.to_string(), escape_value(value)));
+    }
+}
+
+/// Same as `push_optional` for human readable text, where surrounding spaces are
+/// kept since they can be significant (they're written as `\s`)
+fn push_optional_text(pairs: &mut Vec<(String, String)>, key: &str, value: &str) {
+    if !value.trim().is_empty() {
+        pairs.push((key.to_string(), escape_value(value)));
+    }
+}
+
+/// Same as `push_optional` for semicolon-separated list values
+fn push_optional_list(pairs: &mut Vec<(String, String)>, key: &str, value: &str) {
+    let value = value.trim();
+    if !value.is_empty() {
+        pairs.push((key.to_string(), escape_list_value(value)));
+    }
+}
+
+/// Escapes a string value as described by the desktop entry spec, so it always stays on one line
+pub fn escape_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    let last = value.chars().count().saturating_sub(1);
+    for (index, c) in value.chars().enumerate() {
+        // Parsers trim the spaces around a value, so a leading or trailing one is written as \s
+        if c == ' ' && (index == 0 || index == last) {
+            escaped.push_str("\\s");
+        } else {
+            push_escaped(&mut escaped, c);
+        }
+    }
+    escaped
+}
+
+/// Escapes the items of a list value. Semicolons separate items, so a literal
+/// semicolon inside an item has to be given as `\;` and is kept as is.
+pub fn escape_list_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.peek() == Some(&';') {
+            chars.next();
+            escaped.push_str("\\;");
+        } else {
+            push_escaped(&mut escaped, c);
+        }
+    }
+    escaped
+}
+
+/// Escapes the line breaks of a value that is otherwise written as it is (ex: a kept
+/// unknown key), so it can't start a line of its own
+fn single_line(value: &str) -> String {
+    value.replace('\n', "\\n").replace('\r', "\\r")
+}
+
+fn is_action_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn push_escaped(escaped: &mut String, c: char) {
+    match c {
+        '\\' => escaped.push_str("\\\\"),
+        '\n' => escaped.push_str("\\n"),
+        '\t' => escaped.push_str("\\t"),
+        '\r' => escaped.push_str("\\r"),
+        c => escaped.push(c),
+    }
+}
+
+/// Reverses `escape_value`, also turning the spec's `\s` back into a space.
+/// `\;` is left escaped since it's how list values keep a literal semicolon.
+pub fn unescape_value(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Checks that an application id is a valid D-Bus well-known name (reverse DNS, e.g. org.example.App),
+/// which D-Bus activation needs the .desktop file to be named after
+pub fn is_reverse_dns_name(app_id: &str) -> bool {
+    let elements: Vec<&str> = app_id.split('.').collect();
+    app_id.len() <= 255
+        && elements.len() >= 2
+        && elements.iter().all(|element| {
+            !element.is_empty()
+                && !element.starts_with(|c: char| c.is_ascii_digit())
+                && element.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        })
+}
+
+/// Turns a Name into a file name safe base name: lowercase, spaces and hyphens become a single
+/// hyphen and anything else that isn't alphanumeric is dropped (ex: "My Cool App!" is my-cool-app).
+/// Dots and underscores are kept since they're allowed in desktop file ids (ex: org.example.App).
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.trim().chars() {
+        if c.is_alphanumeric() || c == '.' || c == '_' {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-') && !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "entry".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Checks a locale matches the `lang_COUNTRY@MODIFIER` form used in localized keys,
+/// where the country and modifier are optional (ex: fr, pt_BR, sr_RS@latin)
+pub fn is_valid_locale(locale: &str) -> bool {
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale, None),
+    };
+    let (lang, country) = match locale.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (locale, None),
+    };
+
+    (2..=3).contains(&lang.len())
+        && lang.chars().all(|c| c.is_ascii_lowercase())
+        && country.is_none_or(|country| country.len() == 2 && country.chars().all(|c| c.is_ascii_uppercase()))
+        && modifier.is_none_or(|modifier| !modifier.is_empty() && modifier.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Splits an Exec value into its arguments following the spec's quoting rules. An argument in
+/// double quotes can hold spaces and reserved characters, with `"`, `` ` ``, `$` and `\` inside
+/// them escaped by a backslash (which becomes `\\` once the value itself is escaped).
+pub fn split_exec(exec: &str) -> Result<Vec<String>, CreateDesktopError> {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut chars = exec.trim().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let quoted = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped @ ('"' | '`' | '$' | '\\')) => quoted.push(escaped),
+                            _ => return Err(CreateDesktopError::invalid_field("Exec", exec.trim())),
+                        },
+                        Some(c) => quoted.push(c),
+                        // The closing quote is missing
+                        None => return Err(CreateDesktopError::invalid_field("Exec", exec.trim())),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if let Some(arg) = arg.take() {
+                    args.push(arg);
+                }
+            }
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(arg) = arg {
+        args.push(arg);
+    }
+    Ok(args)
+}
+
+/// Characters the spec reserves in Exec, an argument containing any of them has to be quoted
+const EXEC_RESERVED: [char; 19] = [
+    ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(', ')', '`',
+];
+
+/// Quotes each word of an Exec value that contains whitespace or reserved characters
+/// (ex: `--price=$5` becomes `"--price=\$5"`). Words are split on whitespace, so a program path
+/// with spaces has to be given in shell single quotes (`'/home/me/My Apps/tool' --verbose` becomes
+/// `"/home/me/My Apps/tool" --verbose`). Values that already contain double quotes are left alone,
+/// the user has quoted them already.
+pub fn quote_exec(exec: &str) -> String {
+    let exec = exec.trim();
+    if exec.is_empty() || exec.contains('"') {
+        return exec.to_string();
+    }
+
+    shell_words(exec).iter()
+        .map(|word| quote_exec_arg(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Splits `exec` on whitespace outside of shell single quotes, dropping the quotes
+fn shell_words(exec: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut in_quotes = false;
+    for c in exec.chars() {
+        match c {
+            '\'' => {
+                in_quotes = !in_quotes;
+                word.get_or_insert_with(String::new);
+            }
+            c if c.is_whitespace() && !in_quotes => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// Wraps a single Exec argument in double quotes when it needs them, escaping the
+/// characters that are still special inside quotes
+pub fn quote_exec_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(EXEC_RESERVED) {
+        return arg.to_string();
+    }
+
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Returns the reserved characters the spec says have to be inside double quotes in Exec, but aren't
+fn unquoted_reserved_characters(exec: &str) -> Vec<char> {
+    let mut reserved = Vec::new();
+    let mut in_quotes = false;
+    let mut chars = exec.trim().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '\\' if in_quotes => {
+                chars.next();
+            }
+            c if !in_quotes && !c.is_whitespace() && EXEC_RESERVED.contains(&c) && !reserved.contains(&c) => reserved.push(c),
+            _ => {}
+        }
+    }
+    reserved
+}
+
+/// Checks the field codes in an Exec value, rejecting the ones the spec deprecates
+/// (%d, %D, %n, %N, %v, %m) and more than one of the file / URL codes (%f, %F, %u, %U)
+pub fn validate_exec(exec: &str) -> Result<(), CreateDesktopError> {
+    let mut file_codes = 0;
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        match chars.next() {
+            Some('d' | 'D' | 'n' | 'N' | 'v' | 'm') => return Err(CreateDesktopError::invalid_field("Exec", exec.trim())),
+            Some('f' | 'F' | 'u' | 'U') => file_codes += 1,
+            // %% is a literal percent sign, %i, %c and %k are expanded from the other keys
+            _ => {}
+        }
+    }
+
+    if file_codes > 1 {
+        return Err(CreateDesktopError::invalid_field("Exec", exec.trim()));
+    }
+    Ok(())
+}
+
+/// Parses a Terminal value, accepting the usual spellings of a boolean (true/false, 1/0,
+/// yes/no, any case). Empty is None, so the key is left out and the spec's default of false applies
+pub fn parse_terminal(terminal_app: &str) -> Result<Option<bool>, CreateDesktopError> {
+    match terminal_app.trim().to_ascii_lowercase().as_str() {
+        "" => Ok(None),
+        "true" | "1" | "yes" => Ok(Some(true)),
+        "false" | "0" | "no" => Ok(Some(false)),
+        _ => Err(CreateDesktopError::invalid_field("Terminal", terminal_app.trim())),
+    }
+}
+
+/// Normalizes user supplied keywords into a semicolon-separated list.
+/// Input without semicolons is split on commas or whitespace (e.g. "ide, editor code").
+pub fn normalize_keywords(keywords: &str) -> Result<String, CreateDesktopError> {
+    let keywords = keywords.trim();
+    if keywords.contains(['\n', '\r']) {
+        return Err(CreateDesktopError::invalid_field("Keywords", keywords));
+    }
+    if keywords.is_empty() || keywords.contains(';') {
+        return Ok(keywords.to_string());
+    }
+
+    let keywords: Vec<&str> = keywords
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|keyword| !keyword.is_empty())
+        .collect();
+    Ok(format!("{};", keywords.join(";")))
+}
+
+/// Checks that each entry of a semicolon-separated MimeType list roughly matches `type/subtype`.
+/// The spaces around each entry are dropped (ex: "text/plain; text/html" is "text/plain;text/html;")
+pub fn normalize_mime_types(mime_types: &str) -> Result<String, CreateDesktopError> {
+    let mime_types: Vec<&str> = mime_types.split(';')
+        .map(str::trim)
+        .filter(|mime_type| !mime_type.is_empty())
+        .collect();
+
+    for mime_type in &mime_types {
+        if !is_valid_mime_type(mime_type) {
+            return Err(CreateDesktopError::invalid_field("MimeType", mime_type));
+        }
+    }
+
+    Ok(mime_types.iter().map(|mime_type| format!("{};", mime_type)).collect())
+}
+
+fn is_valid_mime_type(mime_type: &str) -> bool {
+    match mime_type.split_once('/') {
+        Some((media_type, subtype)) => {
+            !media_type.is_empty()
+                && !subtype.is_empty()
+                && !subtype.contains('/')
+                && !mime_type.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+/// Parses the StartupNotify value, accepting `true`/`false` or `yes`/`no` when given
+pub fn parse_startup_notify(startup_notify: &str) -> Result<Option<bool>, CreateDesktopError> {
+    match startup_notify.trim() {
+        "" => Ok(None),
+        "true" | "yes" => Ok(Some(true)),
+        "false" | "no" => Ok(Some(false)),
+        other => Err(CreateDesktopError::invalid_field("StartupNotify", other)),
+    }
+}
+
+/// Whether `program` is an executable file, looked up on PATH when it isn't a path (as TryExec is)
+pub fn is_executable_program(program: &str) -> bool {
+    find_program(program).is_some_and(|path| is_executable(&path))
+}
+
+/// Checks that the program Exec starts is installed and runnable, returning the warning when it isn't
+pub fn verify_exec(exec: &str) -> Option<String> {
+    let program = split_exec(exec).ok()?.into_iter().next()?;
+    match find_program(&program) {
+        None => Some(format!("Exec program {} was not found", program)),
+        Some(path) if !is_executable(&path) => Some(format!("Exec program {} is not executable", path.display())),
+        Some(_) => None,
+    }
+}
+
+/// The comment written above created entries (ex: `# Created by create-desktop-file v0.1.0 on 2024-05-01`)
+pub fn generated_header() -> String {
+    format!("{} v{} on {}", HEADER_PREFIX, env!("CARGO_PKG_VERSION"), today())
+}
+
+/// Today's date in UTC as YYYY-MM-DD
+fn today() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    // Converts days since 1970-01-01 to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// What `check_icon` found out about an Icon value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IconCheck {
+    Unset,
+    /// A path to an existing file
+    File,
+    /// A path to a file that doesn't exist
+    Missing,
+    /// A bare name (ex: firefox), looked up in the icon theme when the entry is shown
+    ThemeName,
+}
+
+/// Icons are either a path to an image or the name of an icon from the theme, only the
+/// first can be checked up front
+pub fn check_icon(icon: &str) -> IconCheck {
+    let icon = icon.trim();
+    if icon.is_empty() {
+        IconCheck::Unset
+    } else if !icon.contains('/') {
+        IconCheck::ThemeName
+    } else if Path::new(icon).is_file() {
+        IconCheck::File
+    } else {
+        IconCheck::Missing
+    }
+}
+
+/// Resolves `program` to an existing file, searching PATH when it isn't a path
+fn find_program(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        return Some(PathBuf::from(program)).filter(|path| path.is_file());
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+}
+
+fn is_executable(path: &Path) -> bool {
+    path.metadata()
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+/// The `Key=value` lines of a .desktop file by their group header and key, comments left out
+fn key_lines(contents: &str) -> BTreeMap<(String, String), String> {
+    let mut group = None;
+    let mut lines = BTreeMap::new();
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            group = Some(trimmed.to_string());
+        } else if !trimmed.starts_with('#')
+            && let Some(group) = &group
+            && let Some((key, _)) = line.split_once('=') {
+            lines.insert((group.clone(), key.trim().to_string()), line.to_string());
+        }
+    }
+    lines
+}
+
+/// Whether `key` can be written as a key: letters, digits and `-`, with an optional `[locale]`
+/// (ex: `Name[fr]` or `X-Vendor-Key`)
+fn is_key(key: &str) -> bool {
+    let (name, locale) = match key.strip_suffix(']').and_then(|key| key.split_once('[')) {
+        Some((name, locale)) => (name, Some(locale)),
+        None => (key, None),
+    };
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && locale.is_none_or(|locale| !locale.is_empty() && !locale.chars().any(|c| c.is_control() || "[]=".contains(c)))
+}
+
+/// Whether `key` is a vendor extension key, `X-` followed by letters, digits and `-`
+pub fn is_extension_key(key: &str) -> bool {
+    key.strip_prefix("X-").is_some_and(|rest| {
+        !rest.is_empty() && rest.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+/// Joins list items into a list value, each followed by a `;` as the spec recommends
+fn join_list(items: &[String]) -> String {
+    items.iter().map(|item| format!("{};", item)).collect()
+}
+
+fn ensure_trailing_semicolon(value: &str) -> String {
+    let value = value.trim();
+    if value.ends_with(';') {
+        value.to_string()
+    } else {
+        format!("{};", value)
+    }
+}
+
+impl Serialize for AppType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for AppType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let app_type = String::deserialize(deserializer)?;
+        AppType::from_str(&app_type).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The JSON shape of an entry, lists are arrays and keys that aren't set are left out.
+/// It isn't checked until `build` is called, so a batch can report each bad entry on its own.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DesktopEntrySpec {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    name: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    localized_names: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    generic_name: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    localized_generic_names: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    comment: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    localized_comments: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    exec: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    try_exec: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    url: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    icon: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none", deserialize_with = "bool_or_string")]
+    terminal: Option<bool>,
+    #[serde(rename = "type")]
+    app_type: AppType,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    categories: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    only_show_in: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    not_show_in: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    mime_type: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none", deserialize_with = "bool_or_string")]
+    startup_notify: Option<bool>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    startup_wm_class: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    keywords: Vec<String>,
+    #[serde(skip_serializing_if = "is_false")]
+    no_display: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    hidden: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    dbus_activatable: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    prefers_non_default_gpu: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    actions: Vec<DesktopAction>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    extra_keys: BTreeMap<String, String>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Accepts `true` as well as `"true"` (or any spelling `parse_terminal` accepts)
+fn bool_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BoolOrString {
+        Bool(bool),
+        String(String),
+    }
+
+    match Option::<BoolOrString>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(BoolOrString::Bool(value)) => Ok(Some(value)),
+        Some(BoolOrString::String(value)) => parse_terminal(&value).map_err(serde::de::Error::custom),
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl From<&DesktopEntry> for DesktopEntrySpec {
+    fn from(entry: &DesktopEntry) -> Self {
+        DesktopEntrySpec {
+            version: entry.spec_version.clone(),
+            name: entry.name.clone(),
+            localized_names: entry.localized_names.clone(),
+            generic_name: entry.generic_name.trim().to_string(),
+            localized_generic_names: entry.localized_generic_names.clone(),
+            comment: entry.comment.trim().to_string(),
+            localized_comments: entry.localized_comments.clone(),
+            exec: entry.exec_path.trim().to_string(),
+            try_exec: entry.try_exec.trim().to_string(),
+            url: entry.url.trim().to_string(),
+            icon: entry.icon_path.trim().to_string(),
+            path: entry.working_dir.clone(),
+            terminal: entry.terminal,
+            app_type: entry.app_type,
+            categories: entry.categories.clone(),
+            only_show_in: split_list(&entry.only_show_in),
+            not_show_in: split_list(&entry.not_show_in),
+            mime_type: split_list(&entry.mime_type),
+            startup_notify: entry.startup_notify,
+            startup_wm_class: entry.startup_wm_class.trim().to_string(),
+            keywords: split_list(&entry.keywords),
+            no_display: entry.no_display,
+            hidden: entry.hidden,
+            dbus_activatable: entry.dbus_activatable,
+            prefers_non_default_gpu: entry.prefers_non_default_gpu,
+            actions: entry.actions.clone(),
+            extra_keys: entry.extra_keys.iter()
+                .map(|(key, value)| (key.clone(), unescape_value(value)))
+                .collect(),
+        }
+    }
+}
+
+impl Serialize for DesktopEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        DesktopEntrySpec::from(self).serialize(serializer)
+    }
+}
+
+impl DesktopEntrySpec {
+    /// Goes through `DesktopEntryBuilder`, so an entry read from JSON is checked like one built from flags
+    pub fn build(self) -> Result<DesktopEntry, BuildError> {
+        let join = |items: &[String]| items.iter().map(|item| format!("{};", item)).collect::<String>();
+
+        let mut builder = DesktopEntry::builder()
+            .name(self.name)
+            .generic_name(self.generic_name)
+            .comment(self.comment)
+            .exec(self.exec)
+            .try_exec(self.try_exec)
+            .url(self.url)
+            .icon(self.icon)
+            .working_dir(self.path)
+            .app_type(self.app_type)
+            .categories(self.categories)
+            .only_show_in(join(&self.only_show_in))
+            .not_show_in(join(&self.not_show_in))
+            .mime_type(join(&self.mime_type))
+            .startup_notify(self.startup_notify)
+            .startup_wm_class(self.startup_wm_class)
+            .keywords(join(&self.keywords))
+            .no_display(self.no_display)
+            .hidden(self.hidden)
+            .dbus_activatable(self.dbus_activatable)
+            .prefers_non_default_gpu(self.prefers_non_default_gpu);
+        if let Some(terminal) = self.terminal {
+            builder = builder.terminal(terminal);
+        }
+        if let Some(version) = self.version {
+            builder = builder.spec_version(version);
+        }
+        for (locale, name) in self.localized_names {
+            builder = builder.localized_name(locale, name);
+        }
+        for (locale, generic_name) in self.localized_generic_names {
+            builder = builder.localized_generic_name(locale, generic_name);
+        }
+        for (locale, comment) in self.localized_comments {
+            builder = builder.localized_comment(locale, comment);
+        }
+        for action in self.actions {
+            builder = builder.action(action);
+        }
+        // Checked like --extra-key, so only X- keys get in and a standard key can't be written twice
+        for (key, value) in self.extra_keys {
+            builder = builder.extension_key(key, value);
+        }
+
+        builder.build()
+    }
+}
+
+impl<'de> Deserialize<'de> for DesktopEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DesktopEntrySpec::deserialize(deserializer)?.build().map_err(serde::de::Error::custom)
+    }
+}
@@ -3,9 +3,14 @@ mod tests {
     
     use std::fs;
     use std::path::PathBuf;
+    use std::str::FromStr;
     use tempfile::tempdir;
-    use crate::{break_here_if_os_not_supported, desktop_entry, path};
-    use crate::modes::run_cli;
+    use crate::{break_here_if_os_not_supported, categories, desktop_entry, desktop_utils, flags, manage, path, user_details};
+    use crate::cli::CliArgs;
+    use crate::modes::{build_entry, edit_entry, run_cli};
+    use crate::desktop_entry::AppType;
+    use crate::app_error::{AppError, ConfigError};
+    use crate::error::{CreateDesktopError, ParseError};
 
     // Helper function to setup a temporary directory for tests
     fn setup_test_dir() -> (tempfile::TempDir, PathBuf) {
@@ -44,14 +49,14 @@ mod tests {
 
         println!("Running cli mode");
         // Run CLI mode with test arguments
-        let result = run_cli(false, args, test_path.to_str().unwrap(), "");
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
 
         println!("Getting result");
         assert!(result.is_ok());
         println!("Result OK");
 
         // Verify the .desktop file was created with correct content
-        let desktop_file_path = test_path.join("TestApp.desktop");
+        let desktop_file_path = test_path.join("testapp.desktop");
         println!("Desktop file path: {}", desktop_file_path.display());
         assert!(desktop_file_path.exists());
         println!("Desktop File Exists");
@@ -70,7 +75,6 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Need to specify --name")]
     fn test_cli_missing_name_flag() {
         let args = vec![
             "CreateDesktopFile".to_string(),
@@ -79,9 +83,10 @@ mod tests {
             "Test Application".to_string(),
         ];
         
-        run_cli(false, args, path::LOCAL_SHARE_APPLICATIONS, path::GLOBAL_SHARE_APPLICATIONS).unwrap();
+        let result = run_cli(false, args, path::LOCAL_SHARE_APPLICATIONS, path::GLOBAL_SHARE_APPLICATIONS, None);
 
-        // TODO: This is not failing or panicing
+        assert!(matches!(result, Err(AppError::Entry(CreateDesktopError::MissingName))));
+        assert!(result.unwrap_err().to_string().contains("--name"));
     }
 
     #[test]
@@ -99,15 +104,32 @@ mod tests {
             "/usr/bin/test".to_string(),
         ];
 
-        let result = run_cli(false, args, test_path.to_str().unwrap(), test_path.to_str().unwrap());
+        let result = run_cli(false, args, test_path.to_str().unwrap(), test_path.to_str().unwrap(), None);
         assert!(result.is_ok());
         
-        let content = fs::read_to_string(test_path.join("TestApp.desktop"))
+        let content = fs::read_to_string(test_path.join("testapp.desktop"))
             .expect("Failed to read desktop file");
         
         assert!(content.contains("Comment=This is a test application with spaces"));
     }
 
+    #[test]
+    fn test_new_refuses_an_invalid_terminal() {
+        let result = desktop_entry::DesktopEntry::new(
+            "TestApp".to_string(),
+            "".to_string(),
+            "/usr/bin/test".to_string(),
+            "".to_string(),
+            "maybe".to_string(),
+            AppType::Application,
+            "".to_string(),
+        );
+        assert!(matches!(
+            result,
+            Err(CreateDesktopError::InvalidField { ref key, ref value }) if key == "Terminal" && value == "maybe"
+        ));
+    }
+
     #[test]
     fn test_desktop_entry_generation() {
         let entry = desktop_entry::DesktopEntry::new(
@@ -116,9 +138,9 @@ mod tests {
             "/usr/bin/test".to_string(),
             "/usr/share/icons/test.png".to_string(),
             "false".to_string(),
-            "Application".to_string(),
+            AppType::Application,
             "Development;".to_string(),
-        );
+        ).unwrap();
 
         let entry_string = entry.to_string();
         assert!(entry_string.contains("[Desktop Entry]"));
@@ -128,16 +150,3107 @@ mod tests {
     }
 
     #[test]
-    fn test_supported_os_check() {
-        let supported_oses = vec!["linux"];
-        
-        // This should not panic on Linux
-        break_here_if_os_not_supported(supported_oses.clone(), &"linux");
-        
-        // Test with unsupported OS
-        let result = std::panic::catch_unwind(|| {
-            break_here_if_os_not_supported(supported_oses, &"windows");
-        });
+    fn test_cli_generic_name_flag() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "Kate".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--generic-name".to_string(),
+            "Text Editor".to_string(),
+            "--comment".to_string(),
+            "Edit text files".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+        assert!(result.is_ok());
+
+        let content = fs::read_to_string(test_path.join("kate.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("GenericName=Text Editor"));
+        // GenericName sits directly before Comment
+        assert!(content.contains("GenericName=Text Editor\nComment=Edit text files\n"));
+    }
+
+    #[test]
+    fn test_desktop_entry_with_generic_name() {
+        let entry = desktop_entry::DesktopEntry::new(
+            "Firefox".to_string(),
+            "".to_string(),
+            "/usr/bin/firefox".to_string(),
+            "".to_string(),
+            "false".to_string(),
+            AppType::Application,
+            "".to_string(),
+        ).unwrap().with_generic_name("Web Browser".to_string());
+
+        let entry_string = entry.to_string();
+        assert_eq!(entry_string.matches("GenericName=").count(), 1);
+        assert!(entry_string.contains("GenericName=Web Browser"));
+    }
+
+    #[test]
+    fn test_desktop_entry_without_generic_name() {
+        let entry = desktop_entry::DesktopEntry::new(
+            "TestApp".to_string(),
+            "Test Comment".to_string(),
+            "/usr/bin/test".to_string(),
+            "".to_string(),
+            "false".to_string(),
+            AppType::Application,
+            "".to_string(),
+        ).unwrap().with_generic_name("  ".to_string());
+
+        assert!(!entry.to_string().contains("GenericName="));
+    }
+
+    #[test]
+    fn test_cli_keywords_flag() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--keywords".to_string(),
+            "ide;editor;code".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+        assert!(result.is_ok());
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("Keywords=ide;editor;code;"));
+    }
+
+    #[test]
+    fn test_desktop_entry_keywords_present_and_absent() {
+        let new_entry = || desktop_entry::DesktopEntry::new(
+            "TestApp".to_string(),
+            "".to_string(),
+            "/usr/bin/test".to_string(),
+            "".to_string(),
+            "false".to_string(),
+            AppType::Application,
+            "".to_string(),
+        ).unwrap();
+
+        assert!(!new_entry().to_string().contains("Keywords="));
+        assert!(!new_entry().with_keywords(" ".to_string()).to_string().contains("Keywords="));
+
+        // An existing trailing semicolon is not doubled up
+        let entry_string = new_entry().with_keywords("search;find;".to_string()).to_string();
+        assert!(entry_string.contains("Keywords=search;find;"));
+        assert!(!entry_string.contains(";;"));
+    }
+
+    #[test]
+    fn test_cli_keywords_split_on_commas_and_whitespace() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--keywords".to_string(),
+            "ide, editor code".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+        assert!(result.is_ok());
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("Keywords=ide;editor;code;"));
+    }
+
+    #[test]
+    fn test_keywords_reject_line_breaks() {
+        assert!(desktop_entry::normalize_keywords("ide\nExec=/bin/false").is_err());
+        // The newline left behind by read_line is not an embedded line break
+        assert_eq!(desktop_entry::normalize_keywords("ide;editor\n").unwrap(), "ide;editor");
+    }
+
+    #[test]
+    fn test_cli_mime_type_flag() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--mime-type".to_string(),
+            "text/plain;image/png".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+        assert!(result.is_ok());
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("MimeType=text/plain;image/png;"));
+    }
+
+    #[test]
+    fn test_mime_types_with_spaces_between_them() {
+        assert_eq!(desktop_entry::normalize_mime_types("text/plain; text/html").unwrap(), "text/plain;text/html;");
+        assert_eq!(desktop_entry::normalize_mime_types(" image/png ;image/jpeg; ").unwrap(), "image/png;image/jpeg;");
+        assert_eq!(desktop_entry::normalize_mime_types("  ").unwrap(), "");
+        assert!(desktop_entry::normalize_mime_types("text/plain; text /html").is_err());
+    }
+
+    #[test]
+    fn test_cli_rejects_invalid_mime_type() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--mime-type".to_string(),
+            "text/plain;pdf".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+        assert!(result.is_err());
+        assert!(!test_path.join("testapp.desktop").exists());
+    }
+
+    #[test]
+    fn test_cli_startup_flags() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--startup-notify".to_string(),
+            "false".to_string(),
+            "--startup-wm-class".to_string(),
+            "test-app".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+        assert!(result.is_ok());
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("StartupNotify=false"));
+        assert!(content.contains("StartupWMClass=test-app"));
+    }
+
+    #[test]
+    fn test_startup_wm_class_position() {
+        let entry = desktop_entry::DesktopEntry::new(
+            "TestApp".to_string(),
+            "".to_string(),
+            "/usr/bin/test".to_string(),
+            "".to_string(),
+            "".to_string(),
+            AppType::Application,
+            "Development;".to_string(),
+        ).unwrap()
+            .with_startup_wm_class("  test-app  ".to_string())
+            .with_keywords("test;".to_string());
+
+        let entry_string = entry.to_string();
+        let lines: Vec<&str> = entry_string.lines().collect();
+        let position = |key: &str| lines.iter().position(|line| line.starts_with(key)).unwrap();
+
+        assert!(lines.contains(&"StartupWMClass=test-app"));
+        assert!(position("Categories=") < position("Keywords="));
+        assert!(position("Keywords=") < position("StartupWMClass="));
+    }
+
+    #[test]
+    fn test_cli_wm_class_alias() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--wm-class".to_string(),
+            "electron-app".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("StartupWMClass=electron-app"));
+    }
+
+    #[test]
+    fn test_startup_keys_omitted_when_unset() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(!content.contains("StartupNotify="));
+        assert!(!content.contains("StartupWMClass="));
+    }
+
+    #[test]
+    fn test_startup_notify_must_be_boolean() {
+        assert_eq!(desktop_entry::parse_startup_notify("true").unwrap(), Some(true));
+        assert_eq!(desktop_entry::parse_startup_notify("yes").unwrap(), Some(true));
+        assert_eq!(desktop_entry::parse_startup_notify("no").unwrap(), Some(false));
+        assert_eq!(desktop_entry::parse_startup_notify("").unwrap(), None);
+        assert!(desktop_entry::parse_startup_notify("True").is_err());
+        assert!(desktop_entry::parse_startup_notify("maybe").is_err());
+    }
+
+    #[test]
+    fn test_desktop_entry_skips_empty_optional_keys() {
+        let entry = desktop_entry::DesktopEntry::new(
+            "TestApp".to_string(),
+            "".to_string(),
+            "/usr/bin/test".to_string(),
+            " ".to_string(),
+            "".to_string(),
+            AppType::default(),
+            "".to_string(),
+        ).unwrap();
+
+        let entry_string = entry.to_string();
+        assert!(entry_string.contains("Name=TestApp"));
+        assert!(entry_string.contains("Exec=/usr/bin/test"));
+        assert!(entry_string.contains("Type=Application"));
+        assert!(!entry_string.contains("Icon="));
+        assert!(!entry_string.contains("Comment="));
+        assert!(!entry_string.contains("Terminal="));
+        assert!(!entry_string.contains("Categories="));
+        assert!(!entry_string.lines().any(|line| line.ends_with('=')));
+    }
+
+    #[test]
+    fn test_desktop_entry_builder() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("TestApp")
+            .exec("/usr/bin/test")
+            .icon("/usr/share/icons/test.png")
+            .terminal(false)
+            .app_type(AppType::Application)
+            .categories(["Development"])
+            .build()
+            .expect("Failed to build desktop entry");
+
+        let entry_string = entry.to_string();
+        assert!(entry_string.contains("Name=TestApp"));
+        assert!(entry_string.contains("Exec=/usr/bin/test"));
+        assert!(entry_string.contains("Icon=/usr/share/icons/test.png"));
+        assert!(entry_string.contains("Terminal=false"));
+        assert!(entry_string.contains("Categories=Development;"));
+    }
+
+    #[test]
+    fn test_desktop_entry_builder_requires_name() {
+        let result = desktop_entry::DesktopEntryBuilder::new()
+            .exec("/usr/bin/test")
+            .build();
         assert!(result.is_err());
+
+        let result = desktop_entry::DesktopEntryBuilder::new()
+            .name("   ")
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_cli_no_display_flag() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--no-display".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("NoDisplay=true"));
+        assert!(content.contains("Exec=/usr/bin/test"));
+    }
+
+    #[test]
+    fn test_no_display_omitted_when_unset() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("TestApp")
+            .categories(["Utility"])
+            .build()
+            .unwrap();
+
+        assert!(!entry.to_string().contains("NoDisplay"));
+        assert!(entry.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_no_display_with_categories_warns() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("TestApp")
+            .categories(["Utility"])
+            .no_display(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(entry.warnings().len(), 1);
+        assert!(entry.warnings()[0].contains("Categories"));
+    }
+
+    #[test]
+    fn test_validate_categories_accepts_registered() {
+        assert!(categories::validate_categories(&categories::parse_categories("Utility;")).is_ok());
+        assert!(categories::validate_categories(&categories::parse_categories("Development;IDE;X-MyVendor;")).is_ok());
+        assert!(categories::validate_categories(&[]).is_ok());
+    }
+
+    #[test]
+    fn test_validate_categories_lists_unknown() {
+        let result = categories::validate_categories(&categories::parse_categories("Development;Utilities;Gaems"));
+        assert_eq!(result, Err(vec!["Utilities".to_string(), "Gaems".to_string()]));
+    }
+
+    #[test]
+    fn test_cli_unknown_categories_still_written() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--categories".to_string(),
+            "Development;Utilities;".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+        assert!(result.is_ok());
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop"))
+            .expect("Failed to read desktop file");
+        assert!(content.contains("Categories=Development;Utilities;"));
+    }
+
+    #[test]
+    fn test_cli_hidden_minimal_entry() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "firefox".to_string(),
+            "--hidden".to_string(),
+            "--no-header".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+        assert!(result.is_ok());
+
+        let content = fs::read_to_string(test_path.join("firefox.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert_eq!(
+            content.lines().collect::<Vec<_>>(),
+            vec!["[Desktop Entry]", "Type=Application", "Version=1.5", "Name=firefox", "Hidden=true"]
+        );
+    }
+
+    #[test]
+    fn test_hidden_omitted_when_unset() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("TestApp")
+            .exec("/usr/bin/test")
+            .build()
+            .unwrap();
+
+        assert!(!entry.to_string().contains("Hidden="));
+    }
+
+    #[test]
+    fn test_escape_value() {
+        assert_eq!(desktop_entry::escape_value("a\\b"), "a\\\\b");
+        assert_eq!(desktop_entry::escape_value("one\ntwo\tthree\r"), "one\\ntwo\\tthree\\r");
+        assert_eq!(desktop_entry::escape_list_value("Foo\\;Bar;Baz;"), "Foo\\;Bar;Baz;");
+    }
+
+    #[test]
+    fn test_comment_with_newline_is_escaped() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("TestApp")
+            .comment("First line\nExec=/bin/false")
+            .exec("/usr/bin/test")
+            .build()
+            .unwrap();
+
+        let entry_string = entry.to_string();
+        assert!(entry_string.contains("Comment=First line\\nExec=/bin/false"));
+        assert_eq!(entry_string.lines().filter(|line| line.starts_with("Exec=")).count(), 1);
+    }
+
+    #[test]
+    fn test_cli_working_dir_flag() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let working_dir = format!("{}/", test_path.display());
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--working-dir".to_string(),
+            working_dir,
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains(&format!("Path={}\n", test_path.display())));
+    }
+
+    #[test]
+    fn test_working_dir_trailing_slashes_and_warning() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("TestApp")
+            .working_dir("/does/not/exist//")
+            .build()
+            .unwrap();
+
+        assert!(entry.to_string().contains("Path=/does/not/exist\n"));
+        assert!(entry.warnings().iter().any(|warning| warning.contains("/does/not/exist")));
+
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("TestApp")
+            .working_dir("/")
+            .build()
+            .unwrap();
+
+        assert!(entry.to_string().contains("Path=/\n"));
+        assert!(entry.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_error_exit_codes_are_grouped() {
+        use crate::app_error::*;
+        let errors: [(AppError, i32); 25] = [
+            (CreateDesktopError::Io(std::io::Error::other("disk full")).into(), EXIT_IO),
+            (CreateDesktopError::Io(std::io::Error::from(std::io::ErrorKind::PermissionDenied)).into(), EXIT_NO_PERMISSION),
+            (CreateDesktopError::MissingName.into(), EXIT_DATA),
+            (CreateDesktopError::invalid_field("StartupNotify", "maybe").into(), EXIT_DATA),
+            (AppError::NeedsRoot, EXIT_NO_PERMISSION),
+            (AppError::UnsupportedOs("windows".to_string()), EXIT_UNAVAILABLE),
+            (CreateDesktopError::MissingUrl.into(), EXIT_DATA),
+            (CreateDesktopError::ConflictingKeys { first: "OnlyShowIn".to_string(), second: "NotShowIn".to_string() }.into(), EXIT_DATA),
+            (AppError::FileExists("TestApp.desktop".to_string()), EXIT_CANT_CREATE),
+            (CreateDesktopError::Parse(ParseError::new(None, "missing Name key")).into(), EXIT_DATA),
+            (AppError::EntryNotFound("Foo.desktop".to_string()), EXIT_NO_INPUT),
+            (CreateDesktopError::ValidationFailed("Foo.desktop".to_string()).into(), EXIT_DATA),
+            (CreateDesktopError::UnknownType("Aplication".to_string()).into(), EXIT_DATA),
+            (AppError::InvalidEntry(Vec::new()), EXIT_DATA),
+            (CreateDesktopError::DuplicateKey("X-Foo".to_string()).into(), EXIT_DATA),
+            (serde_json::from_str::<serde_json::Value>("{").unwrap_err().into(), EXIT_DATA),
+            (AppError::BatchFailed { failed: 1, total: 2 }, EXIT_FAILURE),
+            (toml::from_str::<toml::Table>("=").unwrap_err().into(), EXIT_DATA),
+            (AppError::Usage("unexpected argument '--nmae' found".to_string()), EXIT_USAGE),
+            (CreateDesktopError::NoHomeDir.into(), EXIT_CONFIG),
+            (AppError::NoMatchingEntry { name: "Foo".to_string(), close_matches: Vec::new() }, EXIT_NO_INPUT),
+            (AppError::NotDeleted("foo.desktop".to_string()), EXIT_FAILURE),
+            (CreateDesktopError::UnexpectedUrl(AppType::Directory).into(), EXIT_DATA),
+            (AppError::StdinLine { line: 1, message: "unknown key \"nmae\"".to_string() }, EXIT_USAGE),
+            (AppError::Config { path: "config.toml".to_string(), error: ConfigError::Parse(Box::new(toml::from_str::<toml::Table>("=").unwrap_err())) }, EXIT_CONFIG),
+        ];
+
+        for (e, code) in &errors {
+            assert_eq!(e.exit_code(), *code, "{}", e);
+            // Every code an error exits with is listed by --help
+            assert!(EXIT_CODES.iter().any(|(listed, _)| listed == code));
+        }
+        // An error of the entry reads the same through the program's error
+        assert_eq!(errors[3].0.to_string(), "Invalid value \"maybe\" for StartupNotify");
+    }
+
+    #[test]
+    fn test_cli_two_desktop_actions() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "Browser".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/browser".to_string(),
+            "--action".to_string(),
+            "new-window:Name=New Window:Exec=/usr/bin/browser --new-window".to_string(),
+            "--action".to_string(),
+            "private:Name=Private Window:Exec=/usr/bin/browser --private https://example.com:Icon=private".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("browser.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("Actions=new-window;private;\n"));
+        assert!(content.contains("\n\n[Desktop Action new-window]\nName=New Window\nExec=/usr/bin/browser --new-window\n"));
+        assert!(content.contains("\n\n[Desktop Action private]\nName=Private Window\nExec=/usr/bin/browser --private https://example.com\nIcon=private"));
+        // The action groups come after the main group
+        assert!(content.find("Actions=").unwrap() < content.find("[Desktop Action").unwrap());
+    }
+
+    #[test]
+    fn test_desktop_action_validation() {
+        assert!(desktop_entry::DesktopAction::parse("new window:Name=New Window").is_err());
+        assert!(desktop_entry::DesktopAction::parse("new-window:Exec=/usr/bin/foo").is_err());
+        assert!(desktop_entry::DesktopAction::parse(":Name=New Window").is_err());
+        assert!(desktop_entry::DesktopAction::parse("new-window:New Window").is_err());
+
+        let action = desktop_entry::DesktopAction::parse("new-window:Name=New Window").unwrap();
+        assert_eq!(action.id, "new-window");
+        assert_eq!(action.name, "New Window");
+        assert!(action.exec.is_empty());
+    }
+
+    #[test]
+    fn test_desktop_action_shorthand() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "Browser".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/browser".to_string(),
+            "--action".to_string(),
+            "new-window:New Window:/usr/bin/browser --new-window".to_string(),
+            "--action".to_string(),
+            "docs:Open Docs:/usr/bin/browser https://example.com/docs".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("browser.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("Actions=new-window;docs;\n"));
+        assert!(content.contains("\n\n[Desktop Action new-window]\nName=New Window\nExec=/usr/bin/browser --new-window\n"));
+        assert!(content.contains("\n\n[Desktop Action docs]\nName=Open Docs\nExec=/usr/bin/browser https://example.com/docs"));
+    }
+
+    #[test]
+    fn test_cli_equals_syntax() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name=TestApp".to_string(),
+            "--exec-path=/usr/bin/test".to_string(),
+            "--comment".to_string(),
+            "Multi".to_string(),
+            "word".to_string(),
+            "--categories=Development;".to_string(),
+            "--keywords=a=b".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+        assert!(result.is_ok());
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("Name=TestApp\n"));
+        assert!(content.contains("Exec=/usr/bin/test\n"));
+        assert!(content.contains("Comment=Multi word\n"));
+        assert!(content.contains("Categories=Development;\n"));
+        // Only the first = separates the flag from its value
+        assert!(content.contains("Keywords=a=b;"));
+    }
+
+    #[test]
+    fn test_cli_generic_name_requires_name() {
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--generic-name=Text Editor".to_string(),
+        ];
+
+        let result = run_cli(false, args, "", "", None);
+        assert!(matches!(result, Err(AppError::Entry(CreateDesktopError::MissingName))));
+    }
+
+    #[test]
+    fn test_cli_output_flag() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let output = test_path.join("staging/usr/share/applications/org.example.Test.desktop");
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--global".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--output".to_string(),
+            output.to_str().unwrap().to_string(),
+        ];
+
+        // --global would need root, but --output takes precedence over it
+        let result = run_cli(true, args, "", "", None);
+        assert!(result.is_ok());
+
+        let content = fs::read_to_string(&output)
+            .expect("Failed to read desktop file");
+        assert!(content.contains("Name=TestApp"));
+        assert!(!test_path.join("staging/usr/share/applications/testapp.desktop").exists());
+    }
+
+    #[test]
+    fn test_cli_output_short_flag() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let output = test_path.join("custom.desktop");
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            format!("-o={}", output.display()),
+        ];
+
+        run_cli(false, args, "", "", None).unwrap();
+        assert!(output.exists());
+    }
+
+    #[test]
+    fn test_cli_output_directory_and_stdout() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let staging = test_path.join("staging/applications/");
+
+        let args = |output: &str| vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--output".to_string(),
+            output.to_string(),
+        ];
+
+        // A trailing slash is a directory even before it exists
+        run_cli(false, args(staging.to_str().unwrap()), "", "", None).unwrap();
+        assert!(staging.join("testapp.desktop").exists());
+
+        // An existing directory without one is too
+        let existing_dir = test_path.join("staging");
+        run_cli(false, args(existing_dir.to_str().unwrap()), "", "", None).unwrap();
+        assert!(existing_dir.join("testapp.desktop").exists());
+
+        // - prints the entry, there's no file named - afterwards
+        run_cli(false, args("-"), "", "", None).unwrap();
+        assert!(!std::path::Path::new("-").exists());
+    }
+
+    #[test]
+    fn test_cli_dbus_activatable() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "Example App".to_string(),
+            "--dbus-activatable".to_string(),
+            // The file is named after the bus name as given, the slugified name would lowercase it
+            "--filename".to_string(),
+            "org.example.App".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("org.example.App.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("DBusActivatable=true"));
+        // D-Bus activated entries don't need an Exec
+        assert!(!content.contains("Exec="));
+    }
+
+    #[test]
+    fn test_reverse_dns_name() {
+        assert!(desktop_entry::is_reverse_dns_name("org.example.App"));
+        assert!(desktop_entry::is_reverse_dns_name("org.gnome.Text_Editor"));
+        assert!(!desktop_entry::is_reverse_dns_name("firefox"));
+        assert!(!desktop_entry::is_reverse_dns_name("org..App"));
+        assert!(!desktop_entry::is_reverse_dns_name("org.2example.App"));
+        assert!(!desktop_entry::is_reverse_dns_name("My App.example"));
+    }
+
+    #[test]
+    fn test_build_entry_for_stdout() {
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "Foo".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/foo".to_string(),
+            "--stdout".to_string(),
+        ];
+
+        let entry = build_entry(&CliArgs::from_args(&args).unwrap()).unwrap();
+        let content = entry.to_string();
+
+        assert!(content.contains("Name=Foo"));
+        assert!(content.contains("Exec=/usr/bin/foo"));
+    }
+
+    #[test]
+    fn test_cli_stdout_writes_no_file() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "Foo".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/foo".to_string(),
+            "--stdout".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        assert_eq!(fs::read_dir(&test_path).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_build_entry_stdout_requires_name() {
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/foo".to_string(),
+            "--stdout".to_string(),
+        ];
+
+        assert!(matches!(build_entry(&CliArgs::from_args(&args).unwrap()), Err(AppError::Entry(CreateDesktopError::MissingName))));
+    }
+
+    #[test]
+    fn test_cli_link_entry() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "Docs".to_string(),
+            "--app-type".to_string(),
+            "Link".to_string(),
+            "--url".to_string(),
+            "https://example.com/docs".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("docs.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("Type=Link"));
+        assert!(content.contains("URL=https://example.com/docs"));
+        assert!(!content.contains("Exec="));
+    }
+
+    #[test]
+    fn test_link_entry_requires_url() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("Docs")
+            .app_type(AppType::Link)
+            .build();
+
+        assert!(matches!(entry, Err(CreateDesktopError::MissingUrl)));
+    }
+
+    #[test]
+    fn test_url_only_for_link_entries() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("Docs")
+            .exec("/usr/bin/docs")
+            .url("https://example.com/docs")
+            .build();
+        assert!(matches!(entry, Err(CreateDesktopError::UnexpectedUrl(AppType::Application))));
+
+        // Editing a link into an application drops its URL rather than failing
+        let (_temp_dir, test_path) = setup_test_dir();
+        let desktop_file = test_path.join("docs.desktop");
+        fs::write(&desktop_file, "[Desktop Entry]\nType=Link\nName=Docs\nURL=https://example.com/docs\n").unwrap();
+        let args: Vec<String> = ["CreateDesktopFile", "--app-type", "Application", "--exec-path", "/usr/bin/docs"]
+            .iter().map(|arg| arg.to_string()).collect();
+        let edited = edit_entry(&desktop_file, &CliArgs::from_args(&args).unwrap()).unwrap();
+        assert_eq!(edited.value("Type").as_deref(), Some("Application"));
+        assert!(!edited.to_string().contains("URL="));
+    }
+
+    #[test]
+    fn test_cli_only_show_in_flag() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--only-show-in".to_string(),
+            "KDE;XFCE".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("OnlyShowIn=KDE;XFCE;"));
+        assert!(!content.contains("NotShowIn="));
+    }
+
+    #[test]
+    fn test_cli_not_show_in_flag() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--not-show-in=GNOME;".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("NotShowIn=GNOME;"));
+        assert!(!content.contains("OnlyShowIn="));
+    }
+
+    #[test]
+    fn test_only_show_in_and_not_show_in_conflict() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("TestApp")
+            .only_show_in("KDE;")
+            .not_show_in("GNOME;")
+            .build();
+
+        assert!(matches!(entry, Err(CreateDesktopError::ConflictingKeys { .. })));
+    }
+
+    #[test]
+    fn test_cli_refuses_to_overwrite_existing_file() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let desktop_file = test_path.join("testapp.desktop");
+        fs::write(&desktop_file, "hand edited").unwrap();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+
+        assert!(matches!(result, Err(AppError::FileExists(_))));
+        assert_eq!(fs::read_to_string(&desktop_file).unwrap(), "hand edited");
+    }
+
+    #[test]
+    fn test_cli_rewriting_identical_entry_is_a_no_op() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let desktop_file = test_path.join("testapp.desktop");
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+        ];
+        run_cli(false, args.clone(), test_path.to_str().unwrap(), "", None).unwrap();
+        let written = fs::read_to_string(&desktop_file).unwrap();
+
+        // The same details again succeed without --force and leave the file as it was
+        run_cli(false, args.clone(), test_path.to_str().unwrap(), "", None).unwrap();
+        assert_eq!(fs::read_to_string(&desktop_file).unwrap(), written);
+
+        // Different details still need --force
+        let mut changed = args;
+        changed.extend(["--comment".to_string(), "Changed".to_string()]);
+        let result = run_cli(false, changed, test_path.to_str().unwrap(), "", None);
+        assert!(matches!(result, Err(AppError::FileExists(_))));
+        assert_eq!(fs::read_to_string(&desktop_file).unwrap(), written);
+    }
+
+    #[test]
+    fn test_cli_dry_run_writes_nothing() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--dry-run".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        assert!(!test_path.join("testapp.desktop").exists());
+        assert!(!test_path.exists() || fs::read_dir(&test_path).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn test_cli_dry_run_previews_batch_entries() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let (_json_dir, json_dir) = setup_test_dir();
+        let json_path = json_dir.join("apps.json");
+        fs::write(&json_path, r#"[{"name": "First", "exec": "/usr/bin/first"}]"#).unwrap();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--dry-run".to_string(),
+            "--from-json".to_string(),
+            json_path.to_str().unwrap().to_string(),
+        ];
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+        assert!(!test_path.exists() || fs::read_dir(&test_path).unwrap().next().is_none());
+
+        let entry = desktop_entry::DesktopEntryBuilder::new().name("First").exec("/usr/bin/first").build().unwrap();
+        let preview = crate::modes::dry_run_preview(&test_path.join(entry.file_name()), &entry);
+        assert!(preview.starts_with(&format!("Would write {}:\n[Desktop Entry]\n", test_path.join("first.desktop").display())));
+        assert!(preview.ends_with("Exec=/usr/bin/first\n"));
+    }
+
+    #[test]
+    fn test_cli_force_overwrites_existing_file() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let desktop_file = test_path.join("testapp.desktop");
+        fs::write(&desktop_file, "hand edited").unwrap();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--force".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(&desktop_file).unwrap();
+        assert!(content.contains("Name=TestApp"));
+    }
+
+    #[test]
+    fn test_cli_prefers_non_default_gpu() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestGame".to_string(),
+            "--exec-path".to_string(),
+            "/usr/games/testgame".to_string(),
+            "--prefers-non-default-gpu".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("testgame.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("PrefersNonDefaultGPU=true"));
+        // Games are the usual use, but Categories aren't required for it
+        assert!(!content.contains("Categories="));
+    }
+
+    #[test]
+    fn test_prefers_non_default_gpu_omitted_when_unset() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("TestApp")
+            .build()
+            .unwrap();
+
+        assert!(!entry.to_string().contains("PrefersNonDefaultGPU"));
+    }
+
+    #[test]
+    fn test_terminal_accepts_boolean_aliases() {
+        for (input, expected) in [
+            ("true", Some(true)), ("TRUE", Some(true)), ("1", Some(true)), ("Yes", Some(true)), ("True ", Some(true)),
+            ("false", Some(false)), ("False", Some(false)), ("0", Some(false)), ("no", Some(false)),
+            (" ", None),
+        ] {
+            assert_eq!(desktop_entry::parse_terminal(input).unwrap(), expected, "input {:?}", input);
+        }
+
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--terminal-app".to_string(),
+            "YES".to_string(),
+        ];
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+        let content = fs::read_to_string(test_path.join("testapp.desktop")).unwrap();
+        assert!(content.contains("Terminal=true"));
+    }
+
+    #[test]
+    fn test_terminal_rejects_other_values() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--terminal-app".to_string(),
+            "flase".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+
+        assert!(matches!(&result, Err(AppError::Entry(CreateDesktopError::InvalidField { key, .. })) if key == "--terminal-app"));
+        assert_eq!(result.unwrap_err().to_string(), "Invalid value \"flase\" for --terminal-app");
+        assert!(!test_path.join("testapp.desktop").exists());
+    }
+
+    #[test]
+    fn test_spec_version_written_once_before_name() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("TestApp")
+            .exec("/usr/bin/test")
+            .build()
+            .unwrap();
+        let entry_string = entry.to_string();
+        let lines: Vec<&str> = entry_string.lines().collect();
+
+        assert_eq!(entry_string.matches("Version=").count(), 1);
+        assert_eq!(lines[1], "Type=Application");
+        assert_eq!(lines[2], format!("Version={}", desktop_entry::SPEC_VERSION));
+        assert_eq!(lines[3], "Name=TestApp");
+    }
+
+    #[test]
+    fn test_cli_spec_version_override_and_suppression() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "Override".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--spec-version".to_string(),
+            "1.4".to_string(),
+        ];
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+        let content = fs::read_to_string(test_path.join("override.desktop")).unwrap();
+        assert!(content.contains("[Desktop Entry]\nType=Application\nVersion=1.4\nName=Override"));
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "Aliased".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--version-field=1.3".to_string(),
+        ];
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+        let content = fs::read_to_string(test_path.join("aliased.desktop")).unwrap();
+        assert!(content.contains("\nVersion=1.3\n"));
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "Suppressed".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--no-spec-version".to_string(),
+        ];
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+        let content = fs::read_to_string(test_path.join("suppressed.desktop")).unwrap();
+        assert!(!content.contains("Version="));
+    }
+
+    #[test]
+    fn test_cli_localized_keys() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "Editor".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--name-locale".to_string(),
+            "fr".to_string(),
+            "Éditeur".to_string(),
+            "--name-locale".to_string(),
+            "de".to_string(),
+            "Texteditor".to_string(),
+            "--comment".to_string(),
+            "Edit text".to_string(),
+            "--comment-locale".to_string(),
+            "de".to_string(),
+            "Texte bearbeiten".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("editor.desktop"))
+            .expect("Failed to read desktop file");
+
+        // The unlocalized key comes first, followed by each locale
+        assert!(content.contains("Name=Editor\nName[de]=Texteditor\nName[fr]=Éditeur\n"));
+        assert!(content.contains("Comment=Edit text\nComment[de]=Texte bearbeiten\n"));
+    }
+
+    #[test]
+    fn test_cli_locale_shorthand() {
+        let args: Vec<String> = [
+            "CreateDesktopFile", "--name", "Calculator", "--exec-path", "/usr/bin/test",
+            "--name-locale", "de:Rechner", "--name-locale", "fr", "Calculatrice",
+            "--comment-locale", "fr:Calculatrice: simple",
+        ].iter().map(|arg| arg.to_string()).collect();
+
+        let content = build_entry(&CliArgs::from_args(&args).unwrap()).unwrap().to_string();
+        assert!(content.contains("Name=Calculator\nName[de]=Rechner\nName[fr]=Calculatrice\n"));
+        assert!(content.contains("Comment[fr]=Calculatrice: simple"));
+    }
+
+    #[test]
+    fn test_malformed_locale_rejected() {
+        assert!(desktop_entry::is_valid_locale("fr"));
+        assert!(desktop_entry::is_valid_locale("pt_BR"));
+        assert!(desktop_entry::is_valid_locale("sr_RS@latin"));
+        assert!(!desktop_entry::is_valid_locale("French"));
+        assert!(!desktop_entry::is_valid_locale("fr-FR"));
+        assert!(!desktop_entry::is_valid_locale("fr_fr"));
+
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("Editor")
+            .localized_name("fr-FR", "Éditeur")
+            .build();
+
+        assert!(matches!(entry, Err(CreateDesktopError::InvalidField { key, .. }) if key == "locale"));
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("TestApp")
+            .localized_name("fr", "Application de test")
+            .generic_name("Text Editor")
+            .comment("Edits text\nquickly")
+            .exec("/usr/bin/test --flag")
+            .icon("/usr/share/icons/test.png")
+            .terminal(false)
+            .app_type(AppType::Application)
+            .categories(["Development", "Utility"])
+            .keywords("editor;text;")
+            .mime_type("text/plain;")
+            .startup_notify(Some(true))
+            .startup_wm_class("test")
+            .no_display(true)
+            .prefers_non_default_gpu(true)
+            .action(desktop_entry::DesktopAction::parse("new-window:Name=New Window:Exec=/usr/bin/test --new").unwrap())
+            .build()
+            .unwrap();
+
+        let parsed = desktop_entry::DesktopEntry::from_str(&entry.to_string()).unwrap();
+
+        assert_eq!(parsed, entry);
+    }
+
+    #[test]
+    fn test_parse_skips_comments_and_keeps_unknown_keys() {
+        let contents = "# Generated by hand\n\n[Desktop Entry]\nName=TestApp\n# A comment\nX-Custom=kept\\sas is\nExec=/usr/bin/test\\sarg\n\n[Other Group]\nName=Kept\n";
+
+        let parsed = desktop_entry::DesktopEntry::from_str(contents).unwrap();
+
+        assert_eq!(parsed.value("Exec").as_deref(), Some("/usr/bin/test arg"));
+        assert_eq!(parsed.value("X-Custom").as_deref(), Some("kept as is"));
+        assert_eq!(
+            parsed.to_string(),
+            "# Generated by hand\n[Desktop Entry]\nType=Application\nName=TestApp\nExec=/usr/bin/test arg\nX-Custom=kept\\sas is\n\n[Other Group]\nName=Kept\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let missing_group = desktop_entry::DesktopEntry::from_str("Name=TestApp\n").unwrap_err();
+        assert_eq!(missing_group.line, Some(1));
+
+        let bad_line = desktop_entry::DesktopEntry::from_str("[Desktop Entry]\nName=TestApp\nnot a key\n").unwrap_err();
+        assert_eq!(bad_line.line, Some(3));
+
+        let bad_boolean = desktop_entry::DesktopEntry::from_str("[Desktop Entry]\nName=TestApp\nHidden=maybe\n").unwrap_err();
+        assert_eq!(bad_boolean.to_string(), "line 3: expected true or false for Hidden, found \"maybe\"");
+    }
+
+    #[test]
+    fn test_parse_firefox_style_entry() {
+        let contents = "[Desktop Entry]
+Version=1.0
+Name=Firefox Web Browser
+Name[de]=Firefox-Webbrowser
+Name[fr]=Navigateur Web Firefox
+Comment=Browse the World Wide Web
+Comment[de]=Im Internet surfen
+GenericName=Web Browser
+GenericName[de]=Webbrowser
+Keywords=Internet;WWW;Browser;Web;Explorer
+Keywords[de]=Internet;WWW;Browser;Web;Explorer;Webseite;Site;surfen;online;browsen
+Exec=firefox %u
+Terminal=false
+X-MultipleArgs=false
+Type=Application
+Icon=firefox
+Categories=GNOME;GTK;Network;WebBrowser;
+MimeType=text/html;text/xml;application/xhtml+xml;x-scheme-handler/http;x-scheme-handler/https;
+StartupNotify=true
+Actions=new-window;new-private-window;
+
+[Desktop Action new-window]
+Name=Open a New Window
+Name[de]=Ein neues Fenster öffnen
+Exec=firefox -new-window
+
+[Desktop Action new-private-window]
+Name=Open a New Private Window
+Exec=firefox -private-window
+";
+
+        let parsed = desktop_entry::DesktopEntry::from_str(contents).unwrap();
+
+        assert_eq!(parsed.name(), "Firefox Web Browser");
+        assert_eq!(parsed.value("Name[fr]").as_deref(), Some("Navigateur Web Firefox"));
+        assert_eq!(parsed.value("Exec").as_deref(), Some("firefox %u"));
+        assert_eq!(parsed.value("Terminal").as_deref(), Some("false"));
+        assert_eq!(parsed.value("Categories").as_deref(), Some("GNOME;GTK;Network;WebBrowser;"));
+        // Keys the tool doesn't model, including translations of them, are kept
+        assert_eq!(parsed.value("X-MultipleArgs").as_deref(), Some("false"));
+        assert!(parsed.value("Keywords[de]").is_some());
+
+        let written = parsed.to_string();
+        assert!(written.contains("Actions=new-window;new-private-window;\n"));
+        assert!(written.contains("[Desktop Action new-window]\nName=Open a New Window\nExec=firefox -new-window\nName[de]=Ein neues Fenster öffnen\n"));
+        // Lists get their trailing semicolon, after that writing it back changes nothing
+        assert!(written.contains("Keywords=Internet;WWW;Browser;Web;Explorer;\n"));
+        assert_eq!(desktop_entry::DesktopEntry::from_str(&written).unwrap().to_string(), written);
+    }
+
+    #[test]
+    fn test_parse_rejects_missing_header_and_duplicates() {
+        let missing_header = desktop_entry::DesktopEntry::from_str("# Firefox\nName=Firefox\nExec=firefox %u\n").unwrap_err();
+        assert_eq!(missing_header.to_string(), "line 2: Name is outside of a group");
+
+        let duplicate_key = desktop_entry::DesktopEntry::from_str("[Desktop Entry]\nName=Foo\nExec=foo\nName=Bar\n").unwrap_err();
+        assert_eq!(duplicate_key.to_string(), "line 4: duplicate key Name");
+
+        let duplicate_group = desktop_entry::DesktopEntry::from_str("[Desktop Entry]\nName=Foo\n[Desktop Entry]\nName=Bar\n").unwrap_err();
+        assert_eq!(duplicate_group.line, Some(3));
+    }
+
+    #[test]
+    fn test_cli_edit_only_changes_comment() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let desktop_file = test_path.join("foo.desktop");
+        fs::write(
+            &desktop_file,
+            "[Desktop Entry]\nName=Foo\nComment=Old comment\nExec=/opt/foo/bin/foo --flag\nType=Application\nX-KDE-Custom=kept\n",
+        ).unwrap();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--edit".to_string(),
+            desktop_file.to_str().unwrap().to_string(),
+            "--comment".to_string(),
+            "New comment".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(&desktop_file).expect("Failed to read desktop file");
+
+        assert!(content.contains("Comment=New comment"));
+        assert!(!content.contains("Old comment"));
+        assert!(content.contains("Exec=/opt/foo/bin/foo --flag"));
+        assert!(content.contains("X-KDE-Custom=kept"));
+    }
+
+    #[test]
+    fn test_cli_edit_keeps_untouched_keys_as_written() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let desktop_file = test_path.join("proj.desktop");
+        let original = "[Desktop Entry]\nType=Application\nName=Proj\nComment=Old\nExec=bash -c 'cd ~/proj && make run'\n\
+            GenericName=Project\\sTool\nIcon=proj\nCategories=Development;IDE;\nStartupNotify=false\nX-Vendor-Key=a\\sb\n";
+        fs::write(&desktop_file, original).unwrap();
+
+        let args: Vec<String> = ["CreateDesktopFile", "--edit", desktop_file.to_str().unwrap(), "--comment", "New", "--no-header", "--no-verify"]
+            .iter().map(|arg| arg.to_string()).collect();
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(&desktop_file).unwrap();
+        assert!(content.contains("\nComment=New\n"));
+        for line in original.lines().filter(|line| !line.starts_with("Comment=")) {
+            assert!(content.lines().any(|written| written == line), "{} wasn't kept in\n{}", line, content);
+        }
+        assert!(!content.contains("Terminal="));
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn test_gui_edit_keeps_keys_the_form_didnt_change() {
+        let original = "[Desktop Entry]\nType=Application\nName=Proj\nComment=Old\nExec=bash -c 'cd ~/proj && make run'\n\
+            Icon=proj\nCategories=Development;IDE;\nStartupNotify=false\n";
+        let base = desktop_entry::DesktopEntry::from_str(original).unwrap();
+        // What the form's fields and checkboxes hand back when only the comment was changed
+        let state = crate::AppState {
+            comment: "New".to_string(),
+            terminal_app: "false".to_string(),
+            startup_notify: String::new(),
+            categories: "Development;".to_string(),
+            ..crate::AppState::from_entry(base.clone(), PathBuf::from("proj.desktop"))
+        };
+
+        let entry = crate::gui::edited_entry(&base, &state, AppType::Application, None).build().unwrap();
+        let content = entry.to_string_keeping(original);
+
+        assert!(content.contains("\nComment=New\n"));
+        assert!(content.contains("\nExec=bash -c 'cd ~/proj && make run'\n"));
+        assert!(content.contains("\nCategories=Development;IDE;\n"));
+        assert!(content.contains("\nStartupNotify=false\n"));
+        assert!(!content.contains("Terminal="));
+    }
+
+    #[test]
+    fn test_edit_entry_keeps_fields_without_flags() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let desktop_file = test_path.join("foo.desktop");
+        let original = desktop_entry::DesktopEntryBuilder::new()
+            .name("Foo")
+            .exec("/usr/bin/foo")
+            .app_type(AppType::Application)
+            .categories(["Utility"])
+            .build()
+            .unwrap();
+        fs::write(&desktop_file, original.to_string()).unwrap();
+
+        let args = vec!["CreateDesktopFile".to_string(), "--edit".to_string(), desktop_file.to_str().unwrap().to_string()];
+        let edited = edit_entry(&desktop_file, &CliArgs::from_args(&args).unwrap()).unwrap();
+
+        assert_eq!(edited, original);
+    }
+
+    #[test]
+    fn test_cli_field_code_appended() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "Viewer".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/viewer".to_string(),
+            "--field-code".to_string(),
+            "F".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("viewer.desktop"))
+            .expect("Failed to read desktop file");
+
+        assert!(content.contains("Exec=/usr/bin/viewer %F\n"));
+    }
+
+    #[test]
+    fn test_exec_field_code_validation() {
+        let deprecated = desktop_entry::DesktopEntryBuilder::new()
+            .name("Viewer")
+            .exec("/usr/bin/viewer %d")
+            .build();
+        assert!(matches!(deprecated, Err(CreateDesktopError::InvalidField { key, .. }) if key == "Exec"));
+
+        let conflicting = desktop_entry::DesktopEntryBuilder::new()
+            .name("Viewer")
+            .exec("/usr/bin/viewer %u")
+            .field_code("F")
+            .build();
+        assert!(conflicting.is_err());
+
+        let unknown_code = desktop_entry::DesktopEntryBuilder::new()
+            .name("Viewer")
+            .field_code("x")
+            .build();
+        assert!(unknown_code.is_err());
+
+        // 100%% is a literal percent sign, not a field code
+        assert!(desktop_entry::validate_exec("/usr/bin/viewer --zoom 100%% %f").is_ok());
+    }
+
+    #[test]
+    fn test_exec_untouched_without_field_code() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("Viewer")
+            .exec("/usr/bin/viewer %U")
+            .build()
+            .unwrap();
+
+        assert_eq!(entry.value("Exec").as_deref(), Some("/usr/bin/viewer %U"));
+    }
+
+    #[test]
+    fn test_list_entries() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        fs::write(test_path.join("firefox.desktop"), "[Desktop Entry]\nName=Firefox\nExec=firefox\n").unwrap();
+        fs::write(test_path.join("code.desktop"), "[Desktop Entry]\nType=Application\nName=Visual Studio Code\n").unwrap();
+        fs::write(test_path.join("notes.txt"), "Name=Not an entry\n").unwrap();
+
+        let entries = manage::list_entries(&test_path).unwrap();
+
+        assert_eq!(entries, vec![
+            ("Visual Studio Code".to_string(), test_path.join("code.desktop")),
+            ("Firefox".to_string(), test_path.join("firefox.desktop")),
+        ]);
+    }
+
+    #[test]
+    fn test_listed_entries_check_exec_and_keep_broken_files() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        fs::write(test_path.join("shell.desktop"), "[Desktop Entry]\nType=Application\nName=Shell\nExec=/bin/sh -l\n").unwrap();
+        fs::write(test_path.join("gone.desktop"), "[Desktop Entry]\nType=Application\nName=Gone\nExec=/opt/not-installed/gone\n").unwrap();
+        fs::write(test_path.join("broken.desktop"), "Name=Broken\n").unwrap();
+
+        let entries = manage::listed_entries(&test_path).unwrap();
+
+        assert_eq!(entries, vec![
+            manage::ListedEntry { file_name: "broken.desktop".to_string(), name: "Broken".to_string(), exec: String::new(), exec_found: None },
+            manage::ListedEntry { file_name: "gone.desktop".to_string(), name: "Gone".to_string(), exec: "/opt/not-installed/gone".to_string(), exec_found: Some(false) },
+            manage::ListedEntry { file_name: "shell.desktop".to_string(), name: "Shell".to_string(), exec: "/bin/sh -l".to_string(), exec_found: Some(true) },
+        ]);
+        manage::run_list(false, true, test_path.to_str().unwrap(), test_path.to_str().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_find_installed_prefers_the_local_entry() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let local = test_path.join("local");
+        let global = test_path.join("global");
+        fs::create_dir_all(&local).unwrap();
+        fs::create_dir_all(&global).unwrap();
+        fs::write(global.join("firefox.desktop"), "[Desktop Entry]\nType=Application\nName=Firefox\nExec=firefox\n").unwrap();
+        let (local_dir, global_dir) = (local.to_str().unwrap(), global.to_str().unwrap());
+
+        assert_eq!(manage::find_installed("firefox", local_dir, global_dir).unwrap(), vec![global.join("firefox.desktop")]);
+
+        fs::copy(global.join("firefox.desktop"), local.join("firefox.desktop")).unwrap();
+        assert_eq!(
+            manage::find_installed("Firefox", local_dir, global_dir).unwrap(),
+            vec![local.join("firefox.desktop"), global.join("firefox.desktop")]
+        );
+        manage::run_show("firefox.desktop", true, local_dir, global_dir).unwrap();
+
+        let missing = manage::run_show("firefx", false, local_dir, global_dir);
+        assert!(matches!(missing, Err(AppError::NoMatchingEntry { close_matches, .. }) if close_matches == ["firefox.desktop"]));
+    }
+
+    #[test]
+    fn test_validate_existing_files() {
+        let issues = manage::lint("[Desktop Entry]\nType=Application\nName=Foo\nIcon=/does/not/exist.png\n");
+        assert_eq!(issues, vec![
+            manage::LintIssue { severity: desktop_entry::Severity::Error, line: None, message: "Exec is required for Type=Application entries, use --exec-path".to_string() },
+            manage::LintIssue { severity: desktop_entry::Severity::Warning, line: Some(4), message: "Icon /does/not/exist.png does not exist".to_string() },
+        ]);
+        let unparsed = manage::lint("[Desktop Entry]\nName=Foo\njunk\n");
+        assert_eq!(unparsed.len(), 1);
+        assert_eq!(unparsed[0].line, Some(3));
+
+        let (_temp_dir, test_path) = setup_test_dir();
+        fs::write(test_path.join("good.desktop"), "[Desktop Entry]\nType=Application\nName=Good\nExec=/bin/sh\n").unwrap();
+        fs::write(test_path.join("bad.desktop"), "[Desktop Entry]\nType=Link\nName=Bad\n").unwrap();
+        assert_eq!(manage::run_validate_files(std::slice::from_ref(&test_path)).unwrap(), 1);
+        assert_eq!(manage::run_validate_files(&[test_path.join("good.desktop")]).unwrap(), 0);
+
+        // Without files --validate still checks the written entry
+        let args = |extra: &[&str]| {
+            let mut args = vec!["CreateDesktopFile".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            CliArgs::from_args(&args).unwrap()
+        };
+        assert!(args(&["--validate", "--name", "Foo"]).validates_output());
+        assert_eq!(args(&["--validate", "a.desktop", "b"]).files_to_validate(), Some(&[PathBuf::from("a.desktop"), PathBuf::from("b")][..]));
+    }
+
+    #[test]
+    fn test_list_skips_unreadable_entries() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        fs::write(test_path.join("firefox.desktop"), "[Desktop Entry]\nType=Application\nName=Firefox\nExec=firefox\n").unwrap();
+        std::os::unix::fs::symlink(test_path.join("uninstalled.desktop"), test_path.join("dangling.desktop")).unwrap();
+
+        assert_eq!(manage::list_entries(&test_path).unwrap(), vec![("Firefox".to_string(), test_path.join("firefox.desktop"))]);
+        let listed = manage::listed_entries(&test_path).unwrap();
+        assert_eq!(listed.iter().map(|entry| entry.file_name.as_str()).collect::<Vec<_>>(), ["firefox.desktop"]);
+        manage::run_list(false, false, test_path.to_str().unwrap(), test_path.to_str().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_list_entries_missing_directory() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let entries = manage::list_entries(&test_path.join("does-not-exist")).unwrap();
+
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn test_escaping_round_trip_with_nasty_values() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("Trailing space ")
+            .generic_name(" Leading space")
+            .comment("C:\\Program Files\\tool\tuses\\backslashes\nand lines")
+            .exec("/usr/bin/tool \"--title=$HOME\"")
+            .app_type(AppType::Application)
+            .build()
+            .unwrap();
+        let entry_string = entry.to_string();
+
+        assert!(entry_string.contains("Name=Trailing space\\s\n"));
+        assert!(entry_string.contains("GenericName=\\sLeading space\n"));
+        assert!(entry_string.contains("Comment=C:\\\\Program Files\\\\tool\\tuses\\\\backslashes\\nand lines\n"));
+
+        let parsed = desktop_entry::DesktopEntry::from_str(&entry_string).unwrap();
+        assert_eq!(parsed, entry);
+    }
+
+    #[test]
+    fn test_split_exec_quoting() {
+        assert_eq!(
+            desktop_entry::split_exec("\"/home/me/My Apps/tool\" --name \"a \\\"quoted\\\" \\$word\" %f").unwrap(),
+            vec!["/home/me/My Apps/tool", "--name", "a \"quoted\" $word", "%f"]
+        );
+        assert!(desktop_entry::split_exec("/usr/bin/tool \"unterminated").is_err());
+
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("Tool")
+            .exec("/usr/bin/tool \"--log\" > /tmp/log")
+            .build()
+            .unwrap();
+        assert!(entry.warnings().iter().any(|warning| warning.contains("'>'")));
+    }
+
+    #[test]
+    fn test_delete_entry() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let desktop_file = test_path.join("foo.desktop");
+        fs::write(&desktop_file, "[Desktop Entry]\nName=Foo\n").unwrap();
+
+        manage::run_delete(false, "Foo", true, test_path.to_str().unwrap(), "").unwrap();
+
+        assert!(!desktop_file.exists());
+    }
+
+    #[test]
+    fn test_delete_needs_confirming() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let desktop_file = test_path.join("elsewhere/foo.desktop");
+        fs::create_dir_all(desktop_file.parent().unwrap()).unwrap();
+        fs::write(&desktop_file, "[Desktop Entry]\nName=Foo\nExec=/usr/bin/foo\n").unwrap();
+        let full_path = desktop_file.to_str().unwrap();
+
+        // There's no one to ask outside a terminal, so without --yes nothing is deleted
+        let result = manage::run_delete(false, full_path, false, "", "");
+        assert!(matches!(result, Err(AppError::NotDeleted(_))));
+        assert!(desktop_file.exists());
+
+        manage::run_delete(false, full_path, true, "", "").unwrap();
+        assert!(!desktop_file.exists());
+    }
+
+    #[test]
+    fn test_delete_entry_not_found() {
+        let (_temp_dir, test_path) = setup_test_dir();
+
+        let result = manage::run_delete(false, "Missing", true, test_path.to_str().unwrap(), "");
+
+        assert!(matches!(result, Err(AppError::EntryNotFound(_))));
+        assert_ne!(result.unwrap_err().exit_code(), 0);
+    }
+
+    #[test]
+    fn test_exec_path_with_spaces_is_quoted() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("Tool")
+            .exec("'/home/me/My Apps/tool' --verbose %f")
+            .build()
+            .unwrap();
+
+        assert_eq!(entry.value("Exec").as_deref(), Some("\"/home/me/My Apps/tool\" --verbose %f"));
+        assert_eq!(
+            desktop_entry::split_exec(&entry.value("Exec").unwrap()).unwrap(),
+            vec!["/home/me/My Apps/tool", "--verbose", "%f"]
+        );
+    }
+
+    #[test]
+    fn test_exec_already_quoted_is_untouched() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("Tool")
+            .exec("\"/home/me/My Apps/tool\" --verbose")
+            .build()
+            .unwrap();
+
+        assert_eq!(entry.value("Exec").as_deref(), Some("\"/home/me/My Apps/tool\" --verbose"));
+    }
+
+    #[test]
+    fn test_exec_single_quotes_keep_an_argument_together() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("Tool")
+            .exec("sh -c 'echo hi; sleep 1'")
+            .build()
+            .unwrap();
+        assert_eq!(entry.value("Exec").as_deref(), Some("sh -c \"echo hi; sleep 1\""));
+        assert_eq!(desktop_entry::split_exec(&entry.value("Exec").unwrap()).unwrap(), vec!["sh", "-c", "echo hi; sleep 1"]);
+    }
+
+    #[test]
+    fn test_loaded_exec_is_kept_as_written() {
+        for exec in ["sh -c 'echo hi; sleep 1'", "bash -c 'cd ~/proj && make run'", "/usr/bin/foo --price=$5 %U"] {
+            let contents = format!("[Desktop Entry]\nType=Application\nName=Foo\nExec={}\n", exec);
+            let parsed = desktop_entry::DesktopEntry::from_str(&contents).unwrap();
+            let rebuilt = desktop_entry::DesktopEntryBuilder::from(parsed.clone()).comment("Rebuilt").build().unwrap();
+            assert_eq!(rebuilt.value("Exec").as_deref(), Some(exec));
+            assert!(rebuilt.to_string().contains(&format!("\nExec={}\n", exec)));
+            assert_eq!(desktop_entry::DesktopEntry::from_str(&parsed.to_string()).unwrap().value("Exec").as_deref(), Some(exec));
+        }
+    }
+
+    #[test]
+    fn test_exec_argument_with_dollar_is_quoted() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("Tool")
+            .exec("/usr/bin/tool --price=$5")
+            .build()
+            .unwrap();
+
+        assert_eq!(entry.value("Exec").as_deref(), Some("/usr/bin/tool \"--price=\\$5\""));
+        // The backslash is escaped again when the value is written
+        assert!(entry.to_string().contains("Exec=/usr/bin/tool \"--price=\\\\$5\"\n"));
+    }
+
+    #[test]
+    fn test_validator_result_and_missing_validator() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let desktop_file = test_path.join("foo.desktop");
+        fs::write(&desktop_file, "[Desktop Entry]\nName=Foo\n").unwrap();
+
+        // `true` and `false` stand in for a validator that passes and one that reports errors
+        assert!(desktop_utils::run_validator("true", &desktop_file).is_ok());
+        assert!(matches!(
+            desktop_utils::run_validator("false", &desktop_file),
+            Err(CreateDesktopError::ValidationFailed(_))
+        ));
+        assert!(desktop_utils::run_validator("no-such-desktop-file-validate", &desktop_file).is_ok());
+    }
+
+    #[test]
+    fn test_update_desktop_database_command() {
+        let command = desktop_utils::update_desktop_database_command(std::path::Path::new("/home/me/.local/share/applications"));
+
+        assert_eq!(command.get_program(), "update-desktop-database");
+        assert_eq!(command.get_args().collect::<Vec<_>>(), ["/home/me/.local/share/applications"]);
+    }
+
+    #[test]
+    fn test_refresh_defaults_to_global_installs() {
+        let [update_database, forceupdate] = desktop_utils::refresh_commands(std::path::Path::new("/usr/share/applications"));
+        assert_eq!(update_database.get_program(), "update-desktop-database");
+        assert_eq!(forceupdate.get_program(), "xdg-desktop-menu");
+        assert_eq!(forceupdate.get_args().collect::<Vec<_>>(), ["forceupdate"]);
+
+        let args = |extra: &[&str]| {
+            let mut args = vec!["CreateDesktopFile".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            CliArgs::from_args(&args).unwrap()
+        };
+        assert!(args(&[]).refreshes(true));
+        assert!(!args(&[]).refreshes(false));
+        assert!(args(&["--update-db"]).refreshes(false));
+        assert!(!args(&["--no-refresh"]).refreshes(true));
+        // The last of the pair wins
+        assert!(args(&["--no-refresh", "--refresh"]).refreshes(false));
+    }
+
+    #[test]
+    fn test_app_type_is_parsed_case_insensitively() {
+        assert_eq!(AppType::from_str("link").unwrap(), AppType::Link);
+        assert_eq!(AppType::from_str(" DIRECTORY ").unwrap(), AppType::Directory);
+        assert_eq!(AppType::from_str("").unwrap(), AppType::Application);
+        for app_type in AppType::ALL {
+            assert_eq!(AppType::from_str(&app_type.to_string()).unwrap(), app_type);
+        }
+
+        let entry = build_entry(&CliArgs::from_args(&[
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "Games".to_string(),
+            "--app-type".to_string(),
+            "directory".to_string(),
+        ]).unwrap()).unwrap();
+        assert!(entry.to_string().contains("Type=Directory"));
+    }
+
+    #[test]
+    fn test_unknown_app_type_is_rejected() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--app-type".to_string(),
+            "Aplication".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Unknown type \"Aplication\", expected one of Application, Link, Directory"
+        );
+        assert!(!test_path.join("testapp.desktop").exists());
+        assert!(desktop_entry::DesktopEntry::from_str("[Desktop Entry]\nName=Foo\nType=Aplication\n").is_err());
+    }
+
+    #[test]
+    fn test_categories_are_normalized() {
+        assert_eq!(categories::parse_categories("Development,IDE"), vec!["Development", "IDE"]);
+
+        let entry = build_entry(&CliArgs::from_args(&[
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--categories".to_string(),
+            "Development,IDE".to_string(),
+        ]).unwrap()).unwrap();
+
+        assert!(entry.to_string().lines().any(|line| line == "Categories=Development;IDE;"));
+        assert!(entry.category_problems().is_empty());
+    }
+
+    #[test]
+    fn test_categories_need_a_main_category() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("TestApp")
+            .categories(["IDE"])
+            .build()
+            .unwrap();
+
+        assert!(entry.warnings().iter().any(|warning| warning.contains("no main category")));
+    }
+
+    #[test]
+    fn test_strict_rejects_unknown_categories() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--categories".to_string(),
+            "Development;Utilities;".to_string(),
+            "--strict".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+
+        assert!(matches!(result, Err(AppError::InvalidEntry(issues)) if issues[0].key == "Categories"));
+        assert!(!test_path.join("testapp.desktop").exists());
+    }
+
+    #[test]
+    fn test_malformed_mime_type_warns() {
+        let entry = desktop_entry::DesktopEntryBuilder::new()
+            .name("Viewer")
+            .exec("/usr/bin/viewer %f")
+            .mime_type("text/plain;pdf")
+            .build()
+            .unwrap();
+
+        assert!(entry.to_string().lines().any(|line| line == "MimeType=text/plain;pdf;"));
+        assert_eq!(entry.warnings(), vec!["MimeType has entries that aren't type/subtype: pdf".to_string()]);
+    }
+
+    #[test]
+    fn test_minimal_entry_is_four_lines() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--comment".to_string(),
+            " ".to_string(),
+            "--icon-path".to_string(),
+            "".to_string(),
+            "--categories".to_string(),
+            " ".to_string(),
+            "--no-spec-version".to_string(),
+            "--no-header".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop")).unwrap();
+        assert_eq!(
+            content.lines().collect::<Vec<_>>(),
+            vec!["[Desktop Entry]", "Type=Application", "Name=TestApp", "Exec=/usr/bin/test"]
+        );
+    }
+
+    #[test]
+    fn test_no_display_and_hidden_flags() {
+        let entry_for = |extra: &[&str]| {
+            let mut args = vec!["CreateDesktopFile".to_string(), "--name".to_string(), "TestApp".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            build_entry(&CliArgs::from_args(&args).unwrap())
+        };
+
+        let entry = entry_for(&["--no-display", "--hidden"]).unwrap().to_string();
+        assert!(entry.contains("NoDisplay=true"));
+        assert!(entry.contains("Hidden=true"));
+
+        let entry = entry_for(&["--no-display=false", "--hidden", "no"]).unwrap().to_string();
+        assert!(!entry.contains("NoDisplay="));
+        assert!(!entry.contains("Hidden="));
+
+        let entry = entry_for(&["--no-display", "yes", "--comment", "Hidden from menus"]).unwrap();
+        assert!(entry.to_string().contains("NoDisplay=true"));
+        assert_eq!(entry.value("Comment").as_deref(), Some("Hidden from menus"));
+
+        assert!(matches!(
+            entry_for(&["--hidden=maybe"]),
+            Err(AppError::Entry(CreateDesktopError::InvalidField { key, .. })) if key == "--hidden"
+        ));
+    }
+
+    #[test]
+    fn test_builder_from_desktop_entry() {
+        let entry = desktop_entry::DesktopEntry::builder()
+            .name("Foo")
+            .exec("/usr/bin/foo")
+            .terminal(false)
+            .build()
+            .unwrap();
+        assert!(entry.to_string().contains("Exec=/usr/bin/foo\n"));
+
+        let missing_name: Result<_, desktop_entry::BuildError> = desktop_entry::DesktopEntry::builder()
+            .exec("/usr/bin/foo")
+            .build();
+        assert!(matches!(missing_name, Err(CreateDesktopError::MissingName)));
+    }
+
+    #[test]
+    fn test_unknown_desktop_environment_warns() {
+        let entry = desktop_entry::DesktopEntry::builder()
+            .name("Tool")
+            .exec("/usr/bin/tool")
+            .only_show_in("GNOME;Gnome;X-Cosmic;")
+            .build()
+            .unwrap();
+
+        assert!(entry.to_string().lines().any(|line| line == "OnlyShowIn=GNOME;Gnome;X-Cosmic;"));
+        assert_eq!(entry.warnings(), vec!["OnlyShowIn has unregistered desktop environments: Gnome".to_string()]);
+    }
+
+    #[test]
+    fn test_cli_extra_keys() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+            "--extra-key".to_string(),
+            "X-GNOME-UsesNotifications=true".to_string(),
+            "--extra-key=X-AppImage-Version=1.2".to_string(),
+        ];
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop")).unwrap();
+        assert!(content.ends_with("X-GNOME-UsesNotifications=true\nX-AppImage-Version=1.2\n"));
+    }
+
+    #[test]
+    fn test_extra_keys_are_validated() {
+        let with_extra_keys = |keys: &[&str]| {
+            let mut args = vec!["CreateDesktopFile".to_string(), "--name".to_string(), "TestApp".to_string()];
+            for key in keys {
+                args.push("--extra-key".to_string());
+                args.push(key.to_string());
+            }
+            build_entry(&CliArgs::from_args(&args).unwrap())
+        };
+
+        assert!(matches!(with_extra_keys(&["Vendor-Key=1"]), Err(AppError::Entry(CreateDesktopError::InvalidField { .. }))));
+        assert!(matches!(with_extra_keys(&["X-Vendor_Key=1"]), Err(AppError::Entry(CreateDesktopError::InvalidField { .. }))));
+        assert!(matches!(with_extra_keys(&["X-Vendor-Key"]), Err(AppError::Entry(CreateDesktopError::InvalidField { .. }))));
+        assert!(matches!(
+            with_extra_keys(&["X-Vendor-Key=1", "X-Vendor-Key=2"]),
+            Err(AppError::Entry(CreateDesktopError::DuplicateKey(key))) if key == "X-Vendor-Key"
+        ));
+    }
+
+    #[test]
+    fn test_chmod_exec_makes_the_script_executable() {
+        use std::os::unix::fs::PermissionsExt;
+        use desktop_utils::{chmod_exec, ExecChmod};
+
+        let (_temp_dir, test_path) = setup_test_dir();
+        let script = test_path.join("my-script.sh");
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o640)).unwrap();
+
+        let args: Vec<String> = ["CreateDesktopFile", "--name", "My Script", "--exec-path", &format!("{} --flag", script.display()), "--chmod-exec"]
+            .iter().map(|arg| arg.to_string()).collect();
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+        assert!(test_path.join("my-script.desktop").exists());
+        assert_eq!(fs::metadata(&script).unwrap().permissions().mode() & 0o777, 0o750);
+        assert_eq!(chmod_exec(script.to_str().unwrap()).unwrap(), ExecChmod::Unchanged(script.clone()));
+
+        assert!(matches!(chmod_exec("sh -c true").unwrap(), ExecChmod::Skipped(_)));
+        assert!(matches!(chmod_exec(test_path.to_str().unwrap()).unwrap(), ExecChmod::Skipped(_)));
+        assert!(matches!(chmod_exec(test_path.join("missing.sh").to_str().unwrap()).unwrap(), ExecChmod::Skipped(_)));
+    }
+
+    #[test]
+    fn test_try_exec_warns_when_not_executable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_temp_dir, test_path) = setup_test_dir();
+        let executable = test_path.join("tool");
+        fs::write(&executable, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&executable, fs::Permissions::from_mode(0o755)).unwrap();
+        let not_executable = test_path.join("notes.txt");
+        fs::write(&not_executable, "").unwrap();
+        fs::set_permissions(&not_executable, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let entry_with = |try_exec: &std::path::Path| desktop_entry::DesktopEntry::builder()
+            .name("Tool")
+            .exec("/usr/bin/tool")
+            .try_exec(try_exec.to_str().unwrap())
+            .build()
+            .unwrap();
+
+        let entry = entry_with(&executable);
+        assert!(entry.to_string().contains(&format!("Exec=/usr/bin/tool\nTryExec={}\n", executable.display())));
+        assert!(entry.warnings().is_empty());
+
+        // Still written, only warned about
+        let entry = entry_with(&not_executable);
+        assert!(entry.to_string().contains(&format!("TryExec={}\n", not_executable.display())));
+        assert!(entry.warnings().iter().any(|warning| warning.starts_with("TryExec")));
+    }
+
+    #[test]
+    fn test_path_flag_sets_working_dir() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let entry_with = |extra: &[&str]| {
+            let mut args = vec!["CreateDesktopFile".to_string(), "--name".to_string(), "TestApp".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            build_entry(&CliArgs::from_args(&args).unwrap()).unwrap()
+        };
+
+        let entry = entry_with(&["--path", test_path.to_str().unwrap()]);
+        assert!(entry.to_string().contains(&format!("Path={}\n", test_path.display())));
+        assert!(entry.warnings().is_empty());
+
+        assert!(!entry_with(&["--path", ""]).to_string().contains("Path="));
+        assert!(!entry_with(&[]).to_string().contains("Path="));
+    }
+
+    #[test]
+    fn test_validate_reports_issues_by_key() {
+        use desktop_entry::Severity;
+
+        let (_temp_dir, test_path) = setup_test_dir();
+        let entry = desktop_entry::DesktopEntry::from_str(&format!(
+            "[Desktop Entry]\nName=Tool\nIcon={}/missing.png\nPath={}/missing\nCategories=Gaems;\nMimeType=pdf;\n",
+            test_path.display(),
+            test_path.display()
+        )).unwrap();
+
+        let issues = entry.validate();
+        let severity_of = |key: &str| issues.iter().find(|issue| issue.key == key).map(|issue| issue.severity);
+
+        assert_eq!(severity_of("Exec"), Some(Severity::Error));
+        assert_eq!(severity_of("Icon"), Some(Severity::Warning));
+        assert_eq!(severity_of("Path"), Some(Severity::Warning));
+        assert_eq!(severity_of("Categories"), Some(Severity::Warning));
+        assert_eq!(severity_of("MimeType"), Some(Severity::Warning));
+        assert_eq!(severity_of("Name"), None);
+
+        let link = desktop_entry::DesktopEntry::from_str("[Desktop Entry]\nName=Docs\nType=Link\n").unwrap();
+        assert_eq!(link.validate(), vec![desktop_entry::ValidationIssue {
+            severity: Severity::Error,
+            key: "URL".to_string(),
+            message: "URL is required for Type=Link entries, use --url".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn test_cli_aborts_on_validation_errors() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+        ];
+
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+
+        assert!(matches!(&result, Err(AppError::InvalidEntry(issues)) if issues[0].key == "Exec"));
+        assert_eq!(result.unwrap_err().to_string(), "Exec is required for Type=Application entries, use --exec-path");
+        assert!(!test_path.join("testapp.desktop").exists());
+    }
+
+    #[test]
+    fn test_verify_exec() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_temp_dir, test_path) = setup_test_dir();
+        let executable = test_path.join("tool");
+        fs::write(&executable, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&executable, fs::Permissions::from_mode(0o755)).unwrap();
+        let not_executable = test_path.join("script");
+        fs::write(&not_executable, "").unwrap();
+        fs::set_permissions(&not_executable, fs::Permissions::from_mode(0o644)).unwrap();
+
+        assert_eq!(desktop_entry::verify_exec(&format!("{} --flag %f", executable.display())), None);
+        assert_eq!(desktop_entry::verify_exec("sh -c true"), None);
+        assert_eq!(
+            desktop_entry::verify_exec(&format!("{} --flag", not_executable.display())),
+            Some(format!("Exec program {} is not executable", not_executable.display()))
+        );
+        assert_eq!(
+            desktop_entry::verify_exec("/no/such/dir/bogus --flag"),
+            Some("Exec program /no/such/dir/bogus was not found".to_string())
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip_skips_unset_fields() {
+        let entry = desktop_entry::DesktopEntry::builder()
+            .name("Tool")
+            .comment("Does things")
+            .exec("/usr/bin/tool")
+            .terminal(false)
+            .categories(["Development", "IDE"])
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string_pretty(&entry).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value, serde_json::json!({
+            "version": "1.5",
+            "name": "Tool",
+            "comment": "Does things",
+            "exec": "/usr/bin/tool",
+            "terminal": false,
+            "type": "Application",
+            "categories": ["Development", "IDE"],
+        }));
+
+        let parsed: desktop_entry::DesktopEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, entry);
+    }
+
+    #[test]
+    fn test_json_accepts_terminal_as_string() {
+        let entry: desktop_entry::DesktopEntry = serde_json::from_str(
+            r#"{"name": "Tool", "exec": "/usr/bin/tool", "terminal": "true", "type": "application"}"#
+        ).unwrap();
+        assert!(entry.to_string().contains("Type=Application\n"));
+        assert!(entry.to_string().contains("Terminal=true\n"));
+
+        let entry: desktop_entry::DesktopEntry = serde_json::from_str(r#"{"name": "Tool", "terminal": true}"#).unwrap();
+        assert_eq!(entry.value("Terminal").as_deref(), Some("true"));
+
+        assert!(serde_json::from_str::<desktop_entry::DesktopEntry>(r#"{"name": "Tool", "terminal": "maybe"}"#).is_err());
+        assert!(serde_json::from_str::<desktop_entry::DesktopEntry>(r#"{"exec": "/usr/bin/tool"}"#).is_err());
+    }
+
+    #[test]
+    fn test_actions_cannot_add_lines() {
+        let action = desktop_entry::DesktopAction {
+            id: String::from("open"),
+            name: String::from("Open"),
+            extra_keys: vec![(String::from("Name[de]"), String::from("Offnen\nExec=/bin/false"))],
+            ..desktop_entry::DesktopAction::default()
+        };
+        let entry = desktop_entry::DesktopEntry::builder()
+            .name("TestApp")
+            .exec("/usr/bin/test")
+            .action(action)
+            .build()
+            .unwrap();
+        assert_eq!(entry.to_string().lines().filter(|line| line.starts_with("Exec=")).count(), 1);
+
+        let action = desktop_entry::DesktopAction {
+            id: String::from("open]\nExec=/bin/false\n[x"),
+            name: String::from("Open"),
+            ..desktop_entry::DesktopAction::default()
+        };
+        let result = desktop_entry::DesktopEntry::builder()
+            .name("TestApp")
+            .exec("/usr/bin/test")
+            .action(action)
+            .build();
+        assert!(matches!(result, Err(CreateDesktopError::InvalidField { .. })));
+    }
+
+    #[test]
+    fn test_check_icon() {
+        let dir = std::env::temp_dir().join(format!("create-desktop-file-icon-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let icon = dir.join("app.png");
+        std::fs::write(&icon, b"\x89PNG\r\n\x1a\n").unwrap();
+        let missing = dir.join("missing.png");
+
+        assert_eq!(desktop_entry::check_icon(icon.to_str().unwrap()), desktop_entry::IconCheck::File);
+        assert_eq!(desktop_entry::check_icon(missing.to_str().unwrap()), desktop_entry::IconCheck::Missing);
+        assert_eq!(desktop_entry::check_icon("firefox"), desktop_entry::IconCheck::ThemeName);
+        assert_eq!(desktop_entry::check_icon(" "), desktop_entry::IconCheck::Unset);
+
+        // A missing icon is only a warning, the entry is still built
+        let entry = desktop_entry::DesktopEntry::builder()
+            .name("TestApp")
+            .exec("/usr/bin/test")
+            .icon(missing.to_str().unwrap())
+            .build()
+            .unwrap();
+        assert!(entry.warnings().iter().any(|warning| warning.starts_with("Icon ")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_spec_extra_keys_must_be_extension_keys() {
+        let json = |extra_keys: &str| crate::modes::json_specs(&format!(r#"{{"name": "Foo", "exec": "/usr/bin/foo", "extra_keys": {}}}"#, extra_keys), std::path::Path::new("-"))
+            .unwrap().0.into_iter().next().unwrap().build();
+
+        // A standard key would be written a second time
+        assert!(matches!(json(r#"{"Exec": "rm -rf ~"}"#), Err(CreateDesktopError::InvalidField { key, .. }) if key == "extension key"));
+        // A newline in the key would start a line of its own
+        assert!(matches!(json(r#"{"X-A\nExec": "evil"}"#), Err(CreateDesktopError::InvalidField { .. })));
+        let toml = crate::modes::toml_specs("[entry]\nname = \"Foo\"\n\n[entry.extra_keys]\nName = \"Bar\"\n").unwrap();
+        assert!(matches!(toml.into_iter().next().unwrap().build(), Err(CreateDesktopError::InvalidField { .. })));
+
+        let entry = json(r#"{"X-Vendor-Key": "1"}"#).unwrap();
+        assert!(entry.to_string().ends_with("X-Vendor-Key=1\n"));
+        assert_eq!(entry.to_string().matches("Exec=").count(), 1);
+    }
+
+    #[test]
+    fn test_action_keys_from_json_must_be_keys() {
+        let json = |extra_keys: &str| crate::modes::json_specs(&format!(
+            r#"{{"name": "Foo", "exec": "/usr/bin/foo", "actions": [{{"id": "new", "name": "New", "exec": "/usr/bin/foo --new", "extra_keys": {}}}]}}"#,
+            extra_keys
+        ), std::path::Path::new("-")).unwrap().0.into_iter().next().unwrap().build();
+
+        // A newline or a = in the key would write a key the action wasn't given
+        assert!(matches!(json(r#"[["X-A\nExec", "evil"]]"#), Err(CreateDesktopError::InvalidField { key, .. }) if key == "action key"));
+        assert!(matches!(json(r#"[["Exec=rm -rf ~ #", "x"]]"#), Err(CreateDesktopError::InvalidField { .. })));
+        assert!(matches!(json(r#"[["", "x"]]"#), Err(CreateDesktopError::InvalidField { .. })));
+        assert!(matches!(json(r#"[["Exec", "rm -rf ~"]]"#), Err(CreateDesktopError::DuplicateKey(key)) if key == "Exec"));
+
+        let entry = json(r#"[["Name[fr]", "Nouveau"], ["X-Vendor", "1"]]"#).unwrap();
+        assert!(entry.to_string().ends_with("[Desktop Action new]\nName=New\nExec=/usr/bin/foo --new\nName[fr]=Nouveau\nX-Vendor=1\n"));
+    }
+
+    #[test]
+    fn test_comments_with_an_equals_sign_arent_keys() {
+        let commented = "# Made with --name=Foo\n[Desktop Entry]\nType=Application\nName=Foo\nExec=/usr/bin/foo\n\n[X-Extra]\n# Key=not a key\nKey=value\n";
+        let entry = desktop_entry::DesktopEntry::from_str(commented).unwrap();
+
+        let keys: Vec<String> = entry.key_values().into_iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, ["Type", "Name", "Exec", "[X-Extra] Key"]);
+        let uncommented = desktop_entry::DesktopEntry::from_str(&commented.replace("# Made with --name=Foo\n", "")).unwrap();
+        assert_eq!(entry, uncommented);
+    }
+
+    #[test]
+    fn test_batch_input_cant_go_to_stdout_or_output() {
+        let parse = |args: &[&str]| {
+            let mut full = vec!["CreateDesktopFile".to_string()];
+            full.extend(args.iter().map(|arg| arg.to_string()));
+            CliArgs::from_args(&full)
+        };
+        for source in ["--from-json", "--from-toml"] {
+            assert!(matches!(parse(&[source, "apps", "--stdout"]), Err(AppError::Usage(_))));
+            assert!(matches!(parse(&[source, "apps", "--output", "/tmp/foo.desktop"]), Err(AppError::Usage(_))));
+            assert!(matches!(parse(&[source, "apps", "-o", "-"]), Err(AppError::Usage(_))));
+            assert!(parse(&[source, "apps", "--dry-run"]).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_cli_from_json_creates_each_entry() {
+        let (temp_dir, test_path) = setup_test_dir();
+        let json_path = temp_dir.path().join("apps.json");
+        std::fs::write(&json_path, r#"[
+            {"name": "First", "exec": "/usr/bin/first", "icon": "first", "categories": ["Utility"]},
+            {"comment": "No name"},
+            {"name": "Second", "exec": "/usr/bin/second", "terminal": "true"}
+        ]"#).unwrap();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--local".to_string(),
+            "--from-json".to_string(),
+            json_path.to_str().unwrap().to_string(),
+        ];
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+
+        // The entry without a name fails on its own, the two around it are still written
+        assert!(matches!(result, Err(AppError::BatchFailed { failed: 1, total: 3 })));
+        let first = std::fs::read_to_string(test_path.join("first.desktop")).unwrap();
+        assert!(first.contains("Exec=/usr/bin/first"));
+        assert!(first.contains("Categories=Utility;"));
+        let second = std::fs::read_to_string(test_path.join("second.desktop")).unwrap();
+        assert!(second.contains("Terminal=true"));
+    }
+
+    #[test]
+    fn test_cli_from_json_checks_existing_files_like_a_single_entry() {
+        let (temp_dir, test_path) = setup_test_dir();
+        let json_path = temp_dir.path().join("apps.json");
+        let from_json = |json: &str| {
+            std::fs::write(&json_path, json).unwrap();
+            let args = vec![
+                "CreateDesktopFile".to_string(),
+                "--from-json".to_string(),
+                json_path.to_str().unwrap().to_string(),
+            ];
+            run_cli(false, args, test_path.to_str().unwrap(), "", None)
+        };
+        from_json(r#"[{"name": "Same", "exec": "/usr/bin/same"}]"#).unwrap();
+        let same = fs::read_to_string(test_path.join("same.desktop")).unwrap();
+        fs::write(test_path.join("edited.desktop"), "hand edited").unwrap();
+
+        // The same entry again is left alone, a different file isn't overwritten without --force
+        let result = from_json(r#"[
+            {"name": "New", "exec": "/usr/bin/new"},
+            {"name": "Same", "exec": "/usr/bin/same"},
+            {"name": "Edited", "exec": "/usr/bin/edited"},
+            {"name": "New", "exec": "/usr/bin/other"}
+        ]"#);
+        assert!(matches!(result, Err(AppError::BatchFailed { failed: 2, total: 4 })));
+        assert!(fs::read_to_string(test_path.join("new.desktop")).unwrap().contains("Exec=/usr/bin/new\n"));
+        assert_eq!(fs::read_to_string(test_path.join("same.desktop")).unwrap(), same);
+        assert_eq!(fs::read_to_string(test_path.join("edited.desktop")).unwrap(), "hand edited");
+
+        // A single entry that would overwrite a file is refused before anything is written
+        let result = from_json(r#"{"name": "Edited", "exec": "/usr/bin/edited"}"#);
+        assert!(matches!(result, Err(AppError::FileExists(_))));
+        assert_eq!(fs::read_to_string(test_path.join("edited.desktop")).unwrap(), "hand edited");
+    }
+
+    #[test]
+    fn test_cli_from_json_single_object_with_overrides() {
+        let (temp_dir, test_path) = setup_test_dir();
+        let json_path = temp_dir.path().join("app.json");
+        std::fs::write(&json_path, r#"{"name": "Tool", "exec": "/usr/bin/tool", "comment": "From JSON", "icon": "tool", "nmae": "typo"}"#).unwrap();
+
+        let (specs, is_array) = crate::modes::json_specs(&fs::read_to_string(&json_path).unwrap(), &json_path).unwrap();
+        assert_eq!((specs.len(), is_array), (1, false));
+
+        // Flags win over the JSON, the keys they don't set are kept
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--from-json".to_string(),
+            json_path.to_str().unwrap().to_string(),
+            "--comment".to_string(),
+            "From flags".to_string(),
+            "--no-header".to_string(),
+        ];
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+        let content = fs::read_to_string(test_path.join("tool.desktop")).unwrap();
+        assert!(content.contains("Comment=From flags\n"));
+        assert!(content.contains("Icon=tool\n"));
+
+        // A single entry's error is reported as is, before anything is written
+        std::fs::write(&json_path, r#"{"comment": "No name"}"#).unwrap();
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--from-json".to_string(),
+            json_path.to_str().unwrap().to_string(),
+        ];
+        assert!(matches!(run_cli(false, args, test_path.to_str().unwrap(), "", None), Err(AppError::Entry(CreateDesktopError::MissingName))));
+        assert_eq!(manage::list_entries(&test_path).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_cli_writes_generated_header() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--local".to_string(),
+            "--name".to_string(),
+            "TestApp".to_string(),
+            "--exec-path".to_string(),
+            "/usr/bin/test".to_string(),
+        ];
+        run_cli(false, args.clone(), test_path.to_str().unwrap(), "", None).unwrap();
+
+        let content = fs::read_to_string(test_path.join("testapp.desktop")).unwrap();
+        let mut lines = content.lines();
+        let header = lines.next().unwrap();
+        assert!(header.starts_with(&format!("# Created by create-desktop-file v{} on ", env!("CARGO_PKG_VERSION"))));
+        assert_eq!(header.len(), desktop_entry::generated_header().len());
+        assert_eq!(lines.next(), Some("[Desktop Entry]"));
+
+        let mut args = args;
+        args.extend(["--force".to_string(), "--no-header".to_string()]);
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+        let content = fs::read_to_string(test_path.join("testapp.desktop")).unwrap();
+        assert!(content.starts_with("[Desktop Entry]\n"));
+    }
+
+    #[test]
+    fn test_leading_comments_are_kept() {
+        let contents = "# Created by create-desktop-file v0.1.0 on 2024-05-01\n# Edited by hand\n[Desktop Entry]\nType=Application\nName=Foo\nExec=foo\n";
+        let entry = desktop_entry::DesktopEntry::from_str(contents).unwrap();
+        assert_eq!(entry.to_string(), contents);
+
+        // A new run replaces its own header and leaves the other comments alone
+        let written = entry.with_generated_header().to_string();
+        assert_eq!(written.matches(desktop_entry::HEADER_PREFIX).count(), 1);
+        assert!(written.contains(&format!("{}\n# Edited by hand\n[Desktop Entry]", desktop_entry::generated_header())));
+    }
+
+    #[test]
+    fn test_export_json_reads_back_with_from_json() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args: Vec<String> = ["CreateDesktopFile", "--name", "Foo", "--exec-path", "/usr/bin/foo", "--path", "/tmp",
+            "--categories", "Utility", "--no-verify", "--export-json"]
+            .iter().map(|arg| arg.to_string()).collect();
+        run_cli(false, args.clone(), test_path.to_str().unwrap(), "", None).unwrap();
+        assert!(test_path.join("foo.desktop").exists());
+
+        let entry = build_entry(&CliArgs::from_args(&args).unwrap()).unwrap();
+        let destination = test_path.join("foo.desktop");
+        let exported = crate::modes::ExportedEntry::new(&entry, Some(&destination), true);
+        let json = serde_json::to_value(&exported).unwrap();
+        assert_eq!(json["destination"], destination.to_str().unwrap());
+        assert_eq!(json["written"], true);
+        // The working directory keeps its own "path" key
+        assert_eq!(json["path"], "/tmp");
+
+        let (specs, is_array) = crate::modes::json_specs(&json.to_string(), std::path::Path::new("-")).unwrap();
+        assert!(!is_array);
+        assert_eq!(specs.into_iter().next().unwrap().build().unwrap(), entry);
+    }
+
+    #[test]
+    fn test_toml_entry_tables() {
+        let single = crate::modes::toml_specs(r#"
+[entry]
+name = "Foo"
+exec = "/usr/bin/foo"
+type = "Application"
+categories = ["Utility"]
+
+[entry.localized_names]
+de = "Fu"
+
+[[entry.actions]]
+id = "new-window"
+name = "New Window"
+exec = "/usr/bin/foo --new-window"
+"#).unwrap();
+        assert_eq!(single.len(), 1);
+        let entry = single.into_iter().next().unwrap().build().unwrap().to_string();
+        assert!(entry.contains("Name[de]=Fu\n"));
+        assert!(entry.contains("[Desktop Action new-window]\nName=New Window\n"));
+
+        let several = crate::modes::toml_specs("[[entry]]\nname = \"Foo\"\n\n[[entry]]\nname = \"Bar\"\n").unwrap();
+        assert_eq!(several.len(), 2);
+
+        // A value of the wrong type is reported with the key it's under
+        let e = crate::modes::toml_specs("[[entry]]\nname = \"Foo\"\n\n[[entry]]\nname = \"Bar\"\nterminal = \"maybe\"\n").unwrap_err();
+        assert!(matches!(&e, AppError::Toml { key: Some(key), .. } if key == "entry[1].terminal"));
+        assert!(e.to_string().starts_with("Failed to read entry[1].terminal of the TOML entries"));
+    }
+
+    #[test]
+    fn test_cli_from_toml_creates_each_app() {
+        let (temp_dir, test_path) = setup_test_dir();
+        let toml_path = temp_dir.path().join("apps.toml");
+        std::fs::write(&toml_path, r#"
+[[app]]
+name = "First"
+exec = "/usr/bin/first"
+categories = ["Utility"]
+
+[[app]]
+name = "Second"
+exec = "/usr/bin/second"
+terminal = true
+"#).unwrap();
+
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--local".to_string(),
+            "--from-toml".to_string(),
+            toml_path.to_str().unwrap().to_string(),
+        ];
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+
+        let first = std::fs::read_to_string(test_path.join("first.desktop")).unwrap();
+        assert!(first.lines().any(|line| line == "Name=First"));
+        let second = std::fs::read_to_string(test_path.join("second.desktop")).unwrap();
+        assert!(second.lines().any(|line| line == "Name=Second"));
+        assert!(second.lines().any(|line| line == "Terminal=true"));
+
+        // An app without a name is refused like a JSON entry without one
+        std::fs::write(&toml_path, "[[app]]\nexec = \"/usr/bin/third\"\n").unwrap();
+        let args = vec![
+            "CreateDesktopFile".to_string(),
+            "--from-toml".to_string(),
+            toml_path.to_str().unwrap().to_string(),
+        ];
+        let result = run_cli(false, args, test_path.to_str().unwrap(), "", None);
+        assert!(matches!(result, Err(AppError::BatchFailed { failed: 1, total: 1 })));
+    }
+
+    #[test]
+    fn test_export_entry_as_json() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        fs::write(
+            test_path.join("foo.desktop"),
+            "[Desktop Entry]\nName=Foo\nExec=/usr/bin/foo %U\nTerminal=false\nType=Application\nCategories=Utility;\n",
+        ).unwrap();
+
+        let entry = manage::read_entry(&test_path, "Foo").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&serde_json::to_string_pretty(&entry).unwrap()).unwrap();
+        assert_eq!(json["name"], "Foo");
+        assert_eq!(json["exec"], "/usr/bin/foo %U");
+        assert_eq!(json["terminal"], false);
+        assert_eq!(json["categories"], serde_json::json!(["Utility"]));
+
+        assert!(matches!(manage::read_entry(&test_path, "Missing"), Err(AppError::EntryNotFound(_))));
+    }
+
+    #[test]
+    fn test_entry_diff() {
+        let old = desktop_entry::DesktopEntry::from_str(
+            "[Desktop Entry]\nName=Foo\nName[de]=Fu\nComment=Old\nExec=/usr/bin/foo\nType=Application\nX-Vendor-Key=one\n\n[Desktop Action new]\nName=New\nExec=/usr/bin/foo --new\n"
+        ).unwrap();
+        let new = desktop_entry::DesktopEntryBuilder::from(old.clone())
+            .comment("New")
+            .localized_name("fr", "Fou")
+            .extension_key("X-Vendor-Key", "two")
+            .icon("foo")
+            .build()
+            .unwrap();
+
+        let changes: Vec<String> = old.diff(&new).iter().map(|change| change.to_string()).collect();
+        assert_eq!(changes, vec![
+            "Comment changed from \"Old\" to \"New\"",
+            "X-Vendor-Key changed from \"one\" to \"two\"",
+            "Name[fr] added as \"Fou\"",
+            "Icon added as \"foo\"",
+        ]);
+        assert!(old.diff(&old).is_empty());
+
+        let without_action = desktop_entry::DesktopEntry::from_str("[Desktop Entry]\nName=Foo\nExec=/usr/bin/foo\n").unwrap();
+        assert!(old.diff(&without_action).iter().any(|change| change.key == "[Desktop Action new] Exec" && change.new.is_none()));
+
+        // Spaces that aren't written and comments above the entry don't make entries different
+        let spaced = desktop_entry::DesktopEntry::from_str("# A comment\n[Desktop Entry]\nName=Foo\nExec= /usr/bin/foo \n").unwrap();
+        assert_eq!(spaced, without_action);
+    }
+
+    #[test]
+    fn test_fully_populated_entry_snapshot() {
+        let entry = desktop_entry::DesktopEntry::builder()
+            .name("Editor")
+            .localized_name("de", "Texteditor")
+            .generic_name("Text Editor")
+            .comment("Edit text files")
+            .localized_comment("fr", "Modifier du texte")
+            .icon("editor")
+            .exec("/usr/bin/editor")
+            .field_code("F")
+            .try_exec("/usr/bin/editor")
+            .working_dir("/tmp")
+            .terminal(false)
+            .categories(["Utility", "TextEditor"])
+            .keywords("text;edit;")
+            .mime_type("text/plain;")
+            .only_show_in("GNOME;KDE;")
+            .startup_notify(Some(true))
+            .startup_wm_class("editor")
+            .no_display(true)
+            .prefers_non_default_gpu(true)
+            .action(desktop_entry::DesktopAction::parse("new-window:New Window:/usr/bin/editor --new-window").unwrap())
+            .extension_key("X-Editor-Flavour", "plain")
+            .build()
+            .unwrap();
+
+        let written = entry.to_string();
+        assert_eq!(written, concat!(
+            "[Desktop Entry]\n",
+            "Type=Application\n",
+            "Version=1.5\n",
+            "Name=Editor\n",
+            "Name[de]=Texteditor\n",
+            "GenericName=Text Editor\n",
+            "Comment=Edit text files\n",
+            "Comment[fr]=Modifier du texte\n",
+            "Icon=editor\n",
+            "Exec=/usr/bin/editor %F\n",
+            "TryExec=/usr/bin/editor\n",
+            "Path=/tmp\n",
+            "Terminal=false\n",
+            "Categories=Utility;TextEditor;\n",
+            "Keywords=text;edit;\n",
+            "MimeType=text/plain;\n",
+            "OnlyShowIn=GNOME;KDE;\n",
+            "StartupNotify=true\n",
+            "StartupWMClass=editor\n",
+            "NoDisplay=true\n",
+            "PrefersNonDefaultGPU=true\n",
+            "Actions=new-window;\n",
+            "X-Editor-Flavour=plain\n",
+            "\n",
+            "[Desktop Action new-window]\n",
+            "Name=New Window\n",
+            "Exec=/usr/bin/editor --new-window\n",
+        ));
+        assert_eq!(desktop_entry::DesktopEntry::from_str(&written).unwrap().to_string(), written);
+    }
+
+    #[test]
+    fn test_entry_from_interactive_answers() {
+        let answers = user_details::Answers {
+            name: "Editor".to_string(),
+            exec_path: "/usr/bin/editor".to_string(),
+            icon_path: "editor".to_string(),
+            terminal: true,
+            categories: vec!["Utility".to_string(), "Development".to_string()],
+            keywords: "text, edit".to_string(),
+            ..user_details::Answers::default()
+        };
+
+        let content = user_details::entry_from_answers(answers).unwrap().to_string();
+        assert!(content.contains("Name=Editor\n"));
+        assert!(content.contains("Exec=/usr/bin/editor\n"));
+        assert!(content.contains("Terminal=true\n"));
+        assert!(content.contains("Categories=Utility;Development;\n"));
+        assert!(content.contains("Keywords=text;edit;\n"));
+
+        let link = user_details::Answers {
+            name: "Docs".to_string(),
+            app_type: AppType::Link,
+            ..user_details::Answers::default()
+        };
+        assert!(matches!(user_details::entry_from_answers(link), Err(AppError::Entry(CreateDesktopError::MissingUrl))));
+    }
+
+    #[test]
+    fn test_commands_stand_for_their_flags() {
+        let parse = |args: &[&str]| {
+            let mut full = vec!["CreateDesktopFile".to_string()];
+            full.extend(args.iter().map(|arg| arg.to_string()));
+            CliArgs::from_args(&full)
+        };
+
+        assert!(parse(&["list", "--global"]).unwrap().list);
+        let delete = parse(&["delete", "My App", "-y"]).unwrap();
+        assert_eq!((delete.delete.as_deref(), delete.yes), (Some("My App"), true));
+        let edit = parse(&["--verbose", "edit", "my-app", "--comment", "New", "comment"]).unwrap();
+        assert_eq!(edit.edit, Some(PathBuf::from("my-app")));
+        assert_eq!(edit.entry.comment().as_deref(), Some("New comment"));
+        assert!(edit.verbose && edit.command.is_none());
+        let create = parse(&["create", "--name", "Foo", "--exec-path", "/usr/bin/foo"]).unwrap();
+        assert_eq!(create.name.as_deref(), Some("Foo"));
+
+        // Unknown flags and commands are usage errors rather than being ignored
+        let unknown = CliArgs::cli().try_get_matches_from(["CreateDesktopFile", "create", "--nmae", "Foo"]);
+        assert_eq!(unknown.unwrap_err().kind(), clap::error::ErrorKind::UnknownArgument);
+        assert!(matches!(parse(&["remove", "Foo"]), Err(AppError::Usage(_))));
+        assert!(CliArgs::cli().try_get_matches_from(["CreateDesktopFile", "delete"]).is_err());
+    }
+
+    #[test]
+    fn test_unknown_flags_fail_unless_lenient() {
+        let parse = |args: &[&str]| {
+            let mut full = vec!["CreateDesktopFile".to_string()];
+            full.extend(args.iter().map(|arg| arg.to_string()));
+            CliArgs::from_args(&full)
+        };
+
+        let typo = parse(&["--name", "Foo", "--exepath", "/usr/bin/foo"]);
+        assert!(matches!(&typo, Err(AppError::Usage(message)) if message.contains("'--exepath'")));
+
+        // --lenient drops the flag and the value after it
+        let lenient = parse(&["--lenient", "--name", "Foo", "--exepath", "/usr/bin/foo", "--bogus=1", "--exec-path", "/usr/bin/foo"]).unwrap();
+        assert_eq!(lenient.name.as_deref(), Some("Foo"));
+        assert_eq!(lenient.entry.exec_path.as_deref(), Some("/usr/bin/foo"));
+        let ignored: Vec<String> = lenient.ignored_flags.iter().map(|flag| flag.to_string()).collect();
+        assert_eq!(ignored, [
+            "ignored the unknown flag --exepath and discarded the value after it, \"/usr/bin/foo\"",
+            "ignored the unknown flag --bogus=1",
+        ]);
+
+        // A value with spaces can't be a flag, even when it starts with dashes
+        let comment = parse(&["--name", "Foo", "--comment", "--verbose shows more", "--stdout"]).unwrap();
+        assert_eq!(comment.entry.comment().as_deref(), Some("--verbose shows more"));
+        assert!(comment.stdout && !comment.verbose);
+    }
+
+    #[test]
+    fn test_cli_args_parse_both_value_forms() {
+        let parse = |args: &[&str]| {
+            let mut full = vec!["CreateDesktopFile".to_string()];
+            full.extend(args.iter().map(|arg| arg.to_string()));
+            CliArgs::from_args(&full)
+        };
+
+        let args = parse(&["--name=Foo", "--exec-path", "/usr/bin/foo", "--comment", "Does", "foo", "things", "--wm-class=foo"]).unwrap();
+        assert_eq!(args.name.as_deref(), Some("Foo"));
+        assert_eq!(args.entry.comment().as_deref(), Some("Does foo things"));
+        assert_eq!(args.entry.startup_wm_class.as_deref(), Some("foo"));
+        assert!(args.has_details());
+
+        // A flag is never taken as the value of the flag before it
+        assert!(matches!(parse(&["--name", "--comment", "Foo"]), Err(AppError::Usage(_))));
+        assert!(matches!(parse(&["--nmae", "Foo"]), Err(AppError::Usage(message)) if message.contains("--nmae")));
+        assert!(matches!(
+            build_entry(&parse(&["--name", "Foo", "--name-locale", "fr"]).unwrap()),
+            Err(AppError::Entry(CreateDesktopError::InvalidField { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_tui_answers_are_trimmed() {
+        let input = "Foo Editor\n\nEdits foo \r\n/usr/bin/foo\nfoo\n\nyes\nlink\nhttps://example.com\nUtility\n\n\nfoo\n";
+        let mut state = crate::AppState::default();
+        user_details::ask_user_to_fill_in_details(&mut state, &mut input.as_bytes()).unwrap();
+
+        assert_eq!(state.name, "Foo Editor");
+        assert_eq!(state.generic_name, "");
+        assert_eq!(state.comment, "Edits foo");
+        assert_eq!(state.exec_path, "/usr/bin/foo");
+        assert_eq!(state.terminal_app, "yes");
+        assert_eq!(state.app_type, "link");
+        assert_eq!(state.url, "https://example.com");
+        assert_eq!(state.categories, "Utility");
+        assert_eq!(state.startup_wm_class, "foo");
+    }
+
+    #[test]
+    fn test_file_name_is_slugified_name() {
+        assert_eq!(desktop_entry::slugify("My Cool App"), "my-cool-app");
+        assert_eq!(desktop_entry::slugify("  Foo -- Bar! (Beta) "), "foo-bar-beta");
+        assert_eq!(desktop_entry::slugify("org.example.App_2"), "org.example.app_2");
+        assert_eq!(desktop_entry::slugify("Éditeur"), "éditeur");
+        assert_eq!(desktop_entry::slugify("!!!"), "entry");
+
+        let entry = desktop_entry::DesktopEntryBuilder::new().name("My Cool App").build().unwrap();
+        assert_eq!(entry.file_name(), "my-cool-app.desktop");
+        assert_eq!(entry.name(), "My Cool App");
+    }
+
+    #[test]
+    fn test_cli_filename_overrides_the_slug() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args = |extra: &[&str]| {
+            let mut args: Vec<String> = ["CreateDesktopFile", "--name", "My Cool App", "--exec-path", "/usr/bin/cool", "--no-verify"].iter().map(|arg| arg.to_string()).collect();
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args
+        };
+
+        run_cli(false, args(&[]), test_path.to_str().unwrap(), "", None).unwrap();
+        let content = fs::read_to_string(test_path.join("my-cool-app.desktop")).unwrap();
+        assert!(content.contains("Name=My Cool App\n"));
+
+        // With or without the extension
+        run_cli(false, args(&["--filename", "cool"]), test_path.to_str().unwrap(), "", None).unwrap();
+        run_cli(false, args(&["--filename=org.example.Cool.desktop"]), test_path.to_str().unwrap(), "", None).unwrap();
+        assert!(test_path.join("cool.desktop").exists());
+        assert!(test_path.join("org.example.Cool.desktop").exists());
+
+        let result = run_cli(false, args(&["--filename", "../cool"]), test_path.to_str().unwrap(), "", None);
+        assert!(matches!(result, Err(AppError::Entry(CreateDesktopError::InvalidField { .. }))));
+    }
+
+    #[test]
+    fn test_short_flags_match_the_long_ones() {
+        let to_args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let short = build_entry(&CliArgs::from_args(&to_args(&[
+            "CreateDesktopFile", "-n", "My App", "-e", "/usr/bin/my-app", "-i", "my-app",
+            "-c", "Does", "things", "-t", "yes", "-C", "Utility",
+        ])).unwrap()).unwrap();
+        let long = build_entry(&CliArgs::from_args(&to_args(&[
+            "CreateDesktopFile", "--name", "My App", "--exec-path", "/usr/bin/my-app", "--icon-path", "my-app",
+            "--comment", "Does things", "--terminal-app", "yes", "--categories", "Utility",
+        ])).unwrap()).unwrap();
+
+        assert_eq!(short, long);
+        assert_eq!(short.name(), "My App");
+
+        let options = CliArgs::from_args(&to_args(&["CreateDesktopFile", "-g", "-l", "-h", "-V", "-o", "-"])).unwrap();
+        assert!(options.global && options.local && options.help && options.version);
+        assert!(CliArgs::help("create-desktop-file").contains("-n, --name <NAME>"));
+    }
+
+    #[test]
+    fn test_cli_short_flags_write_the_file() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        // -c is taken by --comment, so categories are -C
+        let args: Vec<String> = [
+            "CreateDesktopFile", "-l", "-n", "Short App", "-e", "/bin/sh", "-i", "short-app",
+            "-c", "Made", "with", "short", "flags", "-t", "yes", "-C", "Utility",
+        ].iter().map(|arg| arg.to_string()).collect();
+
+        run_cli(false, args, test_path.to_str().unwrap(), "", None).unwrap();
+        let content = fs::read_to_string(test_path.join("short-app.desktop")).unwrap();
+        for line in ["Name=Short App\n", "Exec=/bin/sh\n", "Icon=short-app\n", "Comment=Made with short flags\n", "Terminal=true\n", "Categories=Utility;\n"] {
+            assert!(content.contains(line), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_cli_app_id_names_the_file() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args = |app_id: &str| -> Vec<String> {
+            ["CreateDesktopFile", "--name", "My App", "--exec-path", "/usr/bin/my-app", "--no-verify", "--app-id", app_id]
+                .iter().map(|arg| arg.to_string()).collect()
+        };
+
+        run_cli(false, args("org.example.MyApp"), test_path.to_str().unwrap(), "", None).unwrap();
+        let content = fs::read_to_string(test_path.join("org.example.MyApp.desktop")).unwrap();
+        assert!(content.contains("Name=My App\n"));
+        assert!(content.contains("StartupWMClass=org.example.MyApp\n"));
+
+        for invalid in ["MyApp", "org.example.My App", "org..MyApp", "org.2example.MyApp"] {
+            let result = run_cli(false, args(invalid), test_path.to_str().unwrap(), "", None);
+            assert!(matches!(result, Err(AppError::Entry(CreateDesktopError::InvalidField { key, .. })) if key == flags::APP_ID), "{}", invalid);
+        }
+        assert_eq!(fs::read_dir(&test_path).unwrap().count(), 1);
+
+        // An explicit window class still wins
+        let mut with_class = args("org.example.Other");
+        with_class.extend(["--wm-class".to_string(), "other".to_string()]);
+        let entry = build_entry(&CliArgs::from_args(&with_class).unwrap()).unwrap();
+        assert_eq!(entry.value("StartupWMClass").as_deref(), Some("other"));
+    }
+
+    #[test]
+    fn test_cli_quiet_and_verbose() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args = |extra: &[&str]| -> Vec<String> {
+            let mut args: Vec<String> = ["CreateDesktopFile", "--name", "Foo", "--exec-path", "/usr/bin/foo", "--no-verify", "--force"]
+                .iter().map(|arg| arg.to_string()).collect();
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args
+        };
+
+        assert!(CliArgs::from_args(&args(&["--quiet"])).unwrap().quiet);
+        run_cli(false, args(&["--quiet"]), test_path.to_str().unwrap(), "", None).unwrap();
+        run_cli(false, args(&["--verbose"]), test_path.to_str().unwrap(), "", None).unwrap();
+        assert!(test_path.join("foo.desktop").exists());
+
+        let result = run_cli(false, args(&["--quiet", "--verbose"]), test_path.to_str().unwrap(), "", None);
+        assert!(matches!(result, Err(AppError::Usage(message)) if message.contains("--quiet")));
+    }
+
+    #[test]
+    fn test_cli_edit_finds_installed_entry_by_name() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let args = |extra: &[&str]| -> Vec<String> {
+            let mut args = vec!["CreateDesktopFile".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args
+        };
+        run_cli(false, args(&["--name", "My App", "--exec-path", "/usr/bin/my-app", "--no-verify", "--extra-key", "X-Vendor=1"]), test_path.to_str().unwrap(), "", None).unwrap();
+
+        for target in ["My App", "my-app.desktop"] {
+            run_cli(false, args(&["--edit", target, "--comment", target, "--no-verify"]), test_path.to_str().unwrap(), "", None).unwrap();
+            let content = fs::read_to_string(test_path.join("my-app.desktop")).unwrap();
+            assert!(content.contains(&format!("Comment={}\n", target)));
+            assert!(content.contains("X-Vendor=1\n"));
+        }
+
+        let result = run_cli(false, args(&["--edit", "My Apq", "--comment", "x"]), test_path.to_str().unwrap(), "", None);
+        let e = result.unwrap_err();
+        assert!(matches!(&e, AppError::NoMatchingEntry { close_matches, .. } if close_matches == &["my-app.desktop"]));
+        assert_eq!(e.to_string(), "No installed entry named \"My Apq\", did you mean my-app.desktop?");
+    }
+
+    #[test]
+    fn test_edit_answers_keep_what_the_prompts_dont_ask() {
+        let existing = desktop_entry::DesktopEntry::from_str(
+            "[Desktop Entry]\nType=Application\nName=Foo\nExec=/usr/bin/foo\nCategories=Development;IDE;\nX-Vendor=1\n"
+        ).unwrap();
+        let mut answers = user_details::Answers::from(&existing);
+        assert_eq!(answers.categories, vec!["Development", "IDE"]);
+        assert_eq!(answers.exec_path, "/usr/bin/foo");
+
+        answers.comment = "Edits foo".to_string();
+        answers.categories = vec!["Utility".to_string()];
+        let edited = user_details::edit_from_answers(existing, answers).unwrap();
+        assert_eq!(edited.value("Comment").as_deref(), Some("Edits foo"));
+        assert_eq!(edited.value("Categories").as_deref(), Some("Utility;IDE;"));
+        assert_eq!(edited.value("X-Vendor").as_deref(), Some("1"));
+        assert_eq!(edited.value("Terminal"), None);
+    }
+
+    #[test]
+    fn test_prefix_moves_the_global_directory() {
+        let args = |extra: &[&str]| {
+            let mut args = vec!["CreateDesktopFile".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            CliArgs::from_args(&args)
+        };
+
+        let prefixed = args(&["--global", "--prefix", "/usr/local/"]).unwrap();
+        assert_eq!(prefixed.global_dir(path::GLOBAL_SHARE_APPLICATIONS), "/usr/local/share/applications/");
+        let default = args(&["--global"]).unwrap();
+        assert_eq!(default.global_dir(path::GLOBAL_SHARE_APPLICATIONS), path::GLOBAL_SHARE_APPLICATIONS);
+        let relative = args(&["--global", "--prefix", "build/usr"]).unwrap();
+        let expected = std::env::current_dir().unwrap().join("build/usr/share/applications/");
+        assert_eq!(relative.global_dir(path::GLOBAL_SHARE_APPLICATIONS), expected.to_str().unwrap());
+
+        // The prefix only moves the global directory, so it's meaningless without --global
+        assert!(matches!(args(&["--prefix", "/usr/local"]), Err(AppError::Usage(_))));
+    }
+
+    #[test]
+    fn test_data_home_follows_xdg_data_home() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let default = path::home_dir().unwrap().join(".local/share");
+
+        assert_eq!(path::data_home_from(Some(test_path.clone())).unwrap(), test_path);
+        // A relative $XDG_DATA_HOME is ignored, as the spec says
+        assert_eq!(path::data_home_from(Some(PathBuf::from("relative/data"))).unwrap(), default);
+        assert_eq!(path::data_home_from(None).unwrap(), default);
+
+        // The global directory is a path of its own, it isn't looked up under the home directory
+        assert_eq!(crate::modes::applications_path(true, path::LOCAL_SHARE_APPLICATIONS, "/opt/share/applications/").unwrap(), PathBuf::from("/opt/share/applications/"));
+        let local = test_path.join("applications");
+        assert_eq!(crate::modes::applications_path(false, local.to_str().unwrap(), "").unwrap(), local);
+    }
+
+    #[test]
+    fn test_completions_cover_the_flags() {
+        let bash = CliArgs::completions(clap_complete::Shell::Bash, "create-desktop-file");
+        assert!(bash.contains("--exec-path"));
+        assert!(bash.contains("--global"));
+        assert!(CliArgs::completions(clap_complete::Shell::Fish, "cdf").contains("complete -c cdf"));
+        assert!(CliArgs::completions(clap_complete::Shell::Zsh, "cdf").starts_with("#compdef cdf"));
+    }
+
+    #[test]
+    fn test_config_file_defaults() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let config = test_path.join("config.toml");
+        fs::write(&config, "categories = [\"Development\", \"IDE\"]\nterminal = true\nscope = \"global\"\n").unwrap();
+        let args = |extra: &[&str]| -> Vec<String> {
+            let mut args: Vec<String> = ["CreateDesktopFile", "--name", "Foo", "--exec-path", "/usr/bin/foo", "--no-verify", "--force", "--config", config.to_str().unwrap()]
+                .iter().map(|arg| arg.to_string()).collect();
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args
+        };
+        let parsed = |args: &[String]| -> Result<CliArgs, AppError> {
+            let mut parsed = CliArgs::from_args(args)?;
+            parsed.load_defaults(None)?;
+            Ok(parsed)
+        };
+
+        let entry = build_entry(&parsed(&args(&[])).unwrap()).unwrap();
+        assert_eq!(entry.value("Categories").as_deref(), Some("Development;IDE;"));
+        assert_eq!(entry.value("Terminal").as_deref(), Some("true"));
+        assert!(parsed(&args(&[])).unwrap().is_global());
+
+        // Flags win over the config file
+        let overridden = parsed(&args(&["--terminal-app", "false", "--categories", "Utility", "--local"])).unwrap();
+        let entry = build_entry(&overridden).unwrap();
+        assert_eq!(entry.value("Terminal").as_deref(), Some("false"));
+        assert_eq!(entry.value("Categories").as_deref(), Some("Utility;"));
+        assert!(!overridden.is_global());
+
+        run_cli(false, args(&["--local"]), test_path.to_str().unwrap(), "", None).unwrap();
+        assert!(fs::read_to_string(test_path.join("foo.desktop")).unwrap().contains("Categories=Development;IDE;\n"));
+
+        // --no-config leaves the defaults out, and can't be given with --config
+        let mut ignored: Vec<String> = ["CreateDesktopFile", "--name", "Foo", "--no-config"].iter().map(|arg| arg.to_string()).collect();
+        assert_eq!(build_entry(&parsed(&ignored).unwrap()).unwrap().value("Categories"), None);
+        ignored.extend(["--config".to_string(), config.display().to_string()]);
+        assert!(CliArgs::from_args(&ignored).is_err());
+
+        // A bad value is reported with the file and the line it's on
+        fs::write(&config, "categories = [\"Utility\"]\nterminal = \"maybe\"\n").unwrap();
+        let e = parsed(&args(&[])).unwrap_err();
+        assert!(matches!(e, AppError::Config { .. }));
+        let message = e.to_string();
+        assert!(message.starts_with(&format!("Failed to read the config file {}, ", config.display())), "{}", message);
+        assert!(message.contains("line 2"), "{}", message);
+        // Only the config file is broken, so --version and --help are still parsed
+        let version: Vec<String> = ["CreateDesktopFile", "--version", "--config", config.to_str().unwrap()].iter().map(|arg| arg.to_string()).collect();
+        assert!(CliArgs::from_args(&version).unwrap().version);
+        fs::write(&config, "categorys = [\"Utility\"]\n").unwrap();
+        assert!(matches!(parsed(&args(&[])), Err(AppError::Config { .. })));
+
+        // A --config file that can't be read is reported the same way
+        fs::remove_file(&config).unwrap();
+        let e = parsed(&args(&[])).unwrap_err();
+        assert!(matches!(e, AppError::Config { error: ConfigError::Read(_), .. }));
+        assert!(e.to_string().starts_with(&format!("Failed to read the config file {}, ", config.display())), "{}", e);
+    }
+
+    #[test]
+    fn test_default_config_file_is_read_when_given() {
+        let (_temp_dir, test_path) = setup_test_dir();
+        let default_config = test_path.join("config.toml");
+        let args: Vec<String> = ["CreateDesktopFile", "--name", "Foo", "--exec-path", "/usr/bin/foo", "--no-verify", "--local"]
+            .iter().map(|arg| arg.to_string()).collect();
+
+        // A default file that doesn't exist leaves the defaults out
+        run_cli(false, args.clone(), test_path.to_str().unwrap(), "", Some(&test_path.join("missing.toml"))).unwrap();
+        assert!(!fs::read_to_string(test_path.join("foo.desktop")).unwrap().contains("Categories="));
+
+        fs::write(&default_config, "categories = [\"Utility\"]\n").unwrap();
+        fs::remove_file(test_path.join("foo.desktop")).unwrap();
+        run_cli(false, args, test_path.to_str().unwrap(), "", Some(&default_config)).unwrap();
+        assert!(fs::read_to_string(test_path.join("foo.desktop")).unwrap().contains("\nCategories=Utility;\n"));
+    }
+
+    #[test]
+    fn test_stdin_answers_are_flags() {
+        let args = |extra: &[&str]| -> Vec<String> {
+            let mut args = vec!["CreateDesktopFile".to_string(), "--stdin".to_string(), "--no-verify".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args
+        };
+        let answers = "# piped in\nname=Foo\n\nexec=/usr/bin/foo --new-window\nterminal=false\ncategories = Utility;Development\ntry_exec=foo\n";
+        let parsed = CliArgs::from_args_and_answers(&args(&[]), answers).unwrap();
+        let entry = build_entry(&parsed).unwrap();
+        assert_eq!(entry.name(), "Foo");
+        assert_eq!(entry.value("Exec").as_deref(), Some("/usr/bin/foo --new-window"));
+        assert_eq!(entry.value("Terminal").as_deref(), Some("false"));
+        assert_eq!(entry.value("Categories").as_deref(), Some("Utility;Development;"));
+        assert_eq!(entry.value("TryExec").as_deref(), Some("foo"));
+
+        // The command line wins over the lines
+        let overridden = CliArgs::from_args_and_answers(&args(&["--name", "Bar"]), answers).unwrap();
+        assert_eq!(overridden.name.as_deref(), Some("Bar"));
+
+        let error = CliArgs::from_args_and_answers(&args(&[]), "name=Foo\n# fine\nnmae=Foo\n").unwrap_err();
+        assert!(matches!(&error, AppError::StdinLine { line: 3, .. }));
+        assert_eq!(error.to_string(), "Failed to read line 3 of standard input, unknown key \"nmae\"");
+        assert!(matches!(CliArgs::from_args_and_answers(&args(&[]), "--global\n"), Err(AppError::StdinLine { line: 1, .. })));
+        assert!(matches!(CliArgs::from_args_and_answers(&args(&[]), "global=true\n"), Err(AppError::StdinLine { line: 1, .. })));
+        // The keys are the entry flags the parser knows, aliases included
+        let command = CliArgs::cli();
+        let entry_flags: Vec<&str> = command.get_arguments()
+            .filter(|arg| arg.get_help_heading() == Some("Entry details (require --name)"))
+            .filter_map(|arg| arg.get_long())
+            .collect();
+        assert!(entry_flags.len() >= 30 && entry_flags.contains(&"name"), "{:?}", entry_flags);
+        assert!(entry_flags.iter().all(|long| crate::cli::is_entry_flag(long)));
+        assert!(crate::cli::is_entry_flag("wm-class"));
+        assert!(!crate::cli::is_entry_flag("output"));
+        // Nothing is asked for, so a missing name is an error
+        assert!(matches!(CliArgs::from_args_and_answers(&args(&[]), "exec=foo\n"), Err(AppError::Entry(CreateDesktopError::MissingName))));
+        assert!(CliArgs::from_args(&args(&["--interactive"])).is_err());
+    }
+
+    #[test]
+    fn test_help_has_examples_with_the_program_name() {
+        let help = crate::help_information::help_information(&["/home/me/bin/cdf".to_string()]);
+        assert!(help.starts_with(&format!("create-desktop-file v{}\n", env!("CARGO_PKG_VERSION"))));
+        let examples = &help[help.find("\nExamples:\n").expect("an examples section")..];
+        assert!(examples.contains("  cdf --name \"My App\" --exec-path /usr/bin/my-app"));
+        assert!(examples.contains("  sudo cdf --global"));
+        assert!(examples.contains("  cdf --interactive\n"));
+        assert!(examples.contains("-c is --comment and -C (capital) is --categories"));
+        assert!(examples.contains("  cdf -n \"My App\" -e /usr/bin/my-app -i my-app -C Utility -c Does my app things\n"));
+        assert!(help.contains("\nExit status:\n  0   "));
+        assert!(help.contains("\n  2   The command line is wrong"));
+        assert!(!help.contains("/home/me/bin"));
+
+        assert_eq!(crate::help_information::program_name(&[]), "create-desktop-file");
+    }
+
+    #[test]
+    fn test_supported_os_check() {
+        let supported_oses = vec!["linux"];
+        
+        // This should not fail on Linux
+        assert!(break_here_if_os_not_supported(supported_oses.clone(), &"linux").is_ok());
+        
+        // Test with unsupported OS
+        let result = break_here_if_os_not_supported(supported_oses, &"windows");
+        assert!(matches!(result, Err(AppError::UnsupportedOs(_))));
     }
 }
\ No newline at end of file
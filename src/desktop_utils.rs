@@ -0,0 +1,136 @@
+use std::fs;
+use std::io;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::desktop_entry::{self, DesktopEntry};
+use crate::error::CreateDesktopError;
+
+/// Validator shipped with desktop-file-utils
+pub const DESKTOP_FILE_VALIDATE: &str = "desktop-file-validate";
+
+/// Runs desktop-file-validate on the file at `path` and prints what it found.
+/// Fails when the validator reports errors, a missing validator only prints a note
+pub fn validate_file(path: &Path) -> Result<(), CreateDesktopError> {
+    run_validator(DESKTOP_FILE_VALIDATE, path)
+}
+
+/// Validates an entry that was never written, through a temporary file that is removed afterwards.
+/// The validator checks the file name too, so the temporary file is named after the entry
+pub fn validate_entry(entry: &DesktopEntry) -> Result<(), CreateDesktopError> {
+    let dir = std::env::temp_dir().join(format!("create-desktop-file-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(entry.file_name());
+    let result = fs::write(&path, entry.to_string())
+        .map_err(CreateDesktopError::from)
+        .and_then(|_| validate_file(&path));
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+/// What `chmod_exec` did to the program an Exec line starts
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExecChmod {
+    /// The execute bits were set on the file
+    Changed(PathBuf),
+    /// The file could already be run
+    Unchanged(PathBuf),
+    /// Left alone, with the reason (ex: it's looked up on PATH)
+    Skipped(String),
+}
+
+/// Sets the execute bits on the program `exec` starts (ex: a script that was just written), for
+/// the owner and whoever can read it. Only a path to a plain file the user owns is changed,
+/// a program looked up on PATH is someone else's to install
+pub fn chmod_exec(exec: &str) -> Result<ExecChmod, CreateDesktopError> {
+    let Some(program) = desktop_entry::split_exec(exec)?.into_iter().next() else {
+        return Ok(ExecChmod::Skipped("the entry has no Exec".to_string()));
+    };
+    if !program.contains('/') {
+        return Ok(ExecChmod::Skipped(format!("{} is looked up on PATH, give its path to change it", program)));
+    }
+
+    let path = PathBuf::from(program);
+    let metadata = match fs::symlink_metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(ExecChmod::Skipped(format!("{} doesn't exist", path.display()))),
+        Err(e) => return Err(e.into()),
+    };
+    if !metadata.is_file() {
+        return Ok(ExecChmod::Skipped(format!("{} isn't a plain file", path.display())));
+    }
+    if metadata.uid() != nix::unistd::getuid().as_raw() {
+        return Ok(ExecChmod::Skipped(format!("{} belongs to another user", path.display())));
+    }
+
+    let mode = metadata.permissions().mode();
+    // The owner can always run it, the group and others when they can read it
+    let executable_mode = mode | 0o100 | ((mode & 0o044) >> 2);
+    if executable_mode == mode {
+        return Ok(ExecChmod::Unchanged(path));
+    }
+    fs::set_permissions(&path, fs::Permissions::from_mode(executable_mode))?;
+    Ok(ExecChmod::Changed(path))
+}
+
+pub fn run_validator(program: &str, path: &Path) -> Result<(), CreateDesktopError> {
+    let output = match Command::new(program).arg(path).output() {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            eprintln!("Note: {} was not found on PATH, install desktop-file-utils to validate the entry", program);
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // Findings go to stderr so they don't mix with an entry printed by --stdout
+    eprint!("{}", String::from_utf8_lossy(&output.stdout));
+    eprint!("{}", String::from_utf8_lossy(&output.stderr));
+
+    if output.status.success() {
+        eprintln!("{} passed {}", path.display(), program);
+        Ok(())
+    } else {
+        Err(CreateDesktopError::ValidationFailed(path.display().to_string()))
+    }
+}
+
+/// Cache builder shipped with desktop-file-utils
+pub const UPDATE_DESKTOP_DATABASE: &str = "update-desktop-database";
+
+/// The update-desktop-database invocation that refreshes the MIME cache of `applications_dir`
+pub fn update_desktop_database_command(applications_dir: &Path) -> Command {
+    let mut command = Command::new(UPDATE_DESKTOP_DATABASE);
+    command.arg(applications_dir);
+    command
+}
+
+/// Menu updater shipped with xdg-utils
+pub const XDG_DESKTOP_MENU: &str = "xdg-desktop-menu";
+
+/// The commands that make launchers pick up the entries of `applications_dir`, update-desktop-database
+/// for the MimeType cache and `xdg-desktop-menu forceupdate` for the menus
+pub fn refresh_commands(applications_dir: &Path) -> [Command; 2] {
+    let mut forceupdate = Command::new(XDG_DESKTOP_MENU);
+    forceupdate.arg("forceupdate");
+    [update_desktop_database_command(applications_dir), forceupdate]
+}
+
+/// Runs the [`refresh_commands`] for `applications_dir`, telling `log` what's run and which tools
+/// aren't installed. The entry is written either way, so a tool failing is only a warning.
+/// Returns whether anything was refreshed
+pub fn refresh_desktop_database(applications_dir: &Path, mut log: impl FnMut(String)) -> bool {
+    let mut refreshed = false;
+    for mut command in refresh_commands(applications_dir) {
+        let program = command.get_program().to_string_lossy().to_string();
+        let args: Vec<_> = command.get_args().map(|arg| arg.to_string_lossy().to_string()).collect();
+        log(format!("Running {} {}", program, args.join(" ")));
+        match command.output() {
+            Ok(output) if output.status.success() => refreshed = true,
+            Ok(output) => eprintln!("Warning: {} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => log(format!("{} isn't installed, skipped it", program)),
+            Err(e) => eprintln!("Warning: couldn't run {}: {}", program, e),
+        }
+    }
+    refreshed
+}
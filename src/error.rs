@@ -0,0 +1,107 @@
+use std::{fmt, io};
+use crate::desktop_entry;
+
+/// Why an entry couldn't be built, parsed or written. The errors only the program runs into
+/// (ex: an unsupported OS, a global install without root) are in its own `AppError`, which
+/// `run_cli` and `run_gui` return
+#[derive(Debug)]
+pub enum CreateDesktopError {
+    /// Details were given without a name to create the entry for
+    MissingName,
+    /// A Type=Link entry was given no URL to point to
+    MissingUrl,
+    /// A URL was given for an entry that isn't a Type=Link, which the spec gives no URL key
+    UnexpectedUrl(desktop_entry::AppType),
+    Io(io::Error),
+    /// A key was given a value that can't be written to a desktop entry
+    InvalidField { key: String, value: String },
+    /// Two keys were given that the spec doesn't allow together
+    ConflictingKeys { first: String, second: String },
+    /// An existing .desktop file couldn't be read back
+    Parse(ParseError),
+    /// desktop-file-validate reported errors in the entry
+    ValidationFailed(String),
+    /// A Type that isn't one of the types defined by the spec
+    UnknownType(String),
+    /// A key that may only appear once was given more than once
+    DuplicateKey(String),
+    /// The home directory couldn't be found to install the entry under
+    NoHomeDir,
+}
+
+/// Why a .desktop file couldn't be parsed, and on which line when it's down to a single line
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: Option<usize>, message: impl Into<String>) -> Self {
+        ParseError { line, message: message.into() }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl CreateDesktopError {
+    pub fn invalid_field(key: &str, value: &str) -> Self {
+        CreateDesktopError::InvalidField { key: key.to_string(), value: value.to_string() }
+    }
+}
+
+impl fmt::Display for CreateDesktopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CreateDesktopError::MissingName => write!(f, "A name is required, use --name alongside the other details"),
+            CreateDesktopError::MissingUrl => write!(f, "A URL is required for Type=Link entries, use --url"),
+            CreateDesktopError::UnexpectedUrl(app_type) => {
+                write!(f, "Only Type=Link entries have a URL, leave --url out of a Type={} entry or use --app-type Link", app_type)
+            }
+            CreateDesktopError::Io(e) => write!(f, "{}", e),
+            CreateDesktopError::InvalidField { key, value } => write!(f, "Invalid value {:?} for {}", value, key),
+            CreateDesktopError::ConflictingKeys { first, second } => write!(f, "{} and {} can't be used together", first, second),
+            CreateDesktopError::Parse(e) => write!(f, "Failed to parse .desktop file, {}", e),
+            CreateDesktopError::ValidationFailed(path) => write!(f, "{} failed validation", path),
+            CreateDesktopError::DuplicateKey(key) => write!(f, "{} is given more than once", key),
+            CreateDesktopError::NoHomeDir => write!(f, "Couldn't find your home directory, set $HOME or use --output"),
+            CreateDesktopError::UnknownType(app_type) => write!(
+                f,
+                "Unknown type {:?}, expected one of {}",
+                app_type,
+                desktop_entry::AppType::ALL.map(|app_type| app_type.to_string()).join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CreateDesktopError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CreateDesktopError::Io(e) => Some(e),
+            CreateDesktopError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CreateDesktopError {
+    fn from(e: io::Error) -> Self {
+        CreateDesktopError::Io(e)
+    }
+}
+
+impl From<ParseError> for CreateDesktopError {
+    fn from(e: ParseError) -> Self {
+        CreateDesktopError::Parse(e)
+    }
+}
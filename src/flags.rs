@@ -1,12 +1,13 @@
-// Flags supported by the application
-pub const LOCAL: &str = "--local";
-pub const GLOBAL: &str = "--global";
-pub const HELP: &str = "--help";
-pub const VERSION: &str = "--version";
-pub const NAME: &str = "--name";
-pub const COMMENT: &str = "--comment";
-pub const EXEC_PATH: &str = "--exec-path";
-pub const ICON_PATH: &str = "--icon-path";
+//! The long flags the messages name, ex: "Invalid value \"maybe\" for --hidden"
+pub const NAME_LOCALE: &str = "--name-locale";
+pub const GENERIC_NAME_LOCALE: &str = "--generic-name-locale";
+pub const COMMENT_LOCALE: &str = "--comment-locale";
 pub const TERMINAL_APP: &str = "--terminal-app";
-pub const APP_TYPE: &str = "--app-type";
-pub const CATEGORIES: &str = "--categories";
+pub const APP_ID: &str = "--app-id";
+pub const NO_DISPLAY: &str = "--no-display";
+pub const HIDDEN: &str = "--hidden";
+pub const EXTRA_KEY: &str = "--extra-key";
+pub const DBUS_ACTIVATABLE: &str = "--dbus-activatable";
+pub const PREFERS_NON_DEFAULT_GPU: &str = "--prefers-non-default-gpu";
+pub const FILENAME: &str = "--filename";
+pub const LENIENT: &str = "--lenient";
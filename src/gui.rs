@@ -0,0 +1,673 @@
+//! The GTK form, only built with the `gui` feature (on by default)
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use gtk::prelude::*;
+use gtk::{Application, ApplicationWindow, Button, CheckButton, DropDown, Entry, Expander, FileChooserAction, FileChooserDialog, FileFilter, Grid, Label, MenuButton, Popover};
+use crate::{categories, desktop_entry, desktop_utils, path, AppState};
+use crate::config::Config;
+use crate::desktop_entry::{AppType, DesktopEntry, DesktopEntryBuilder};
+use crate::app_error::AppError;
+use crate::error::CreateDesktopError;
+
+impl AppState {
+    /// Fills the state from an existing entry, so the GUI form starts out with its values
+    pub(crate) fn from_entry(entry: DesktopEntry, edit_path: PathBuf) -> Self {
+        let value = |key: &str| entry.value(key).unwrap_or_default();
+        let state = AppState {
+            name: value("Name"),
+            generic_name: value("GenericName"),
+            comment: value("Comment"),
+            exec_path: value("Exec"),
+            url: value("URL"),
+            icon_path: value("Icon"),
+            working_dir: value("Path"),
+            terminal_app: value("Terminal"),
+            app_type: value("Type"),
+            categories: value("Categories"),
+            keywords: value("Keywords"),
+            mime_type: value("MimeType"),
+            startup_notify: value("StartupNotify"),
+            startup_wm_class: value("StartupWMClass"),
+            no_display: entry.value("NoDisplay").is_some(),
+            dbus_activatable: entry.value("DBusActivatable").is_some(),
+            prefers_non_default_gpu: entry.value("PrefersNonDefaultGPU").is_some(),
+            edit_path: Some(edit_path),
+            ..AppState::default()
+        };
+        AppState { edit_base: Some(entry), ..state }
+    }
+}
+
+pub fn run_gui(local_share_applications: &str, edit_path: Option<PathBuf>, refresh: bool, no_header: bool, defaults: &Config) -> Result<(), AppError> {
+    // With --edit the form starts out filled in from the existing entry
+    let mut initial_state = match &edit_path {
+        Some(edit_path) => {
+            let existing = DesktopEntry::from_str(&fs::read_to_string(edit_path)?)?;
+            AppState::from_entry(existing, edit_path.clone())
+        }
+        // A new entry starts out from the config file's defaults, which the form can still change
+        None => {
+            let mut state = AppState::default();
+            defaults.fill_in(&mut state);
+            state
+        }
+    };
+    initial_state.no_header = no_header;
+    let state = Arc::new(Mutex::new(initial_state));
+    let applications_dir = path::local_applications_dir(local_share_applications)?;
+
+    let app = Application::builder()
+        .application_id("com.catley.createdesktopfile")
+        .build();
+
+    let state_clone = Arc::clone(&state);
+    let form_applications_dir = applications_dir.clone();
+    app.connect_activate(move |app| build_ui(app, &state_clone, &form_applications_dir));
+
+    // Run the GUI application
+    app.run();
+
+    // Entries are only written by the form's Save button, closing the window saves nothing
+    let state_data = state.lock().unwrap();
+
+    // Refresh whichever directory the form saved into
+    if refresh && state_data.saved {
+        let applications_dir = match edit_path.as_ref().and_then(|path| path.parent()) {
+            Some(parent) => parent.to_path_buf(),
+            None => applications_dir,
+        };
+        if desktop_utils::refresh_desktop_database(&applications_dir, |_| {}) {
+            println!("Desktop database refreshed in {}", applications_dir.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn build_ui(app: &Application, state: &Arc<Mutex<AppState>>, applications_dir: &Path) {
+    let grid = Grid::builder()
+        .margin_start(12)
+        .margin_end(12)
+        .margin_top(12)
+        .margin_bottom(12)
+        .row_spacing(6)
+        .column_spacing(12)
+        .build();
+
+    // Create labels and entries with their corresponding field names
+    let entries = [
+        ("name", Label::new(Some("Name:")), Entry::new()),
+        ("generic_name", Label::new(Some("Generic Name:")), Entry::new()),
+        ("comment", Label::new(Some("Comment:")), Entry::new()),
+        ("exec_path", Label::new(Some("Executable Path:")), Entry::new()),
+        ("url", Label::new(Some("URL:")), Entry::new()),
+        ("icon_path", Label::new(Some("Icon Path:")), Entry::new()),
+        ("working_dir", Label::new(Some("Working Directory:")), Entry::new()),
+        ("keywords", Label::new(Some("Keywords:")), Entry::new()),
+        ("mime_type", Label::new(Some("MIME Types:")), Entry::new()),
+        ("startup_wm_class", Label::new(Some("Window Class:")), Entry::new()),
+    ];
+
+
+    // Add labels and entries to the grid
+    for (i, (_, label, entry)) in entries.iter().enumerate() {
+        label.set_halign(gtk::Align::End);
+        grid.attach(label, 0, i as i32, 1, 1);
+        grid.attach(entry, 1, i as i32, 1, 1);
+        entry.set_hexpand(true);
+    }
+
+    // Paths are easier to pick than to type, so the executable and icon fields get a Browse button
+    let icon_filter = FileFilter::new();
+    icon_filter.set_name(Some("Images"));
+    for suffix in ["png", "svg", "xpm", "jpg", "jpeg", "ico"] {
+        icon_filter.add_suffix(suffix);
+    }
+    for (i, (field_name, _, entry)) in entries.iter().enumerate() {
+        match *field_name {
+            "exec_path" => add_browse_button(&grid, i as i32, entry, "Select Executable", Some("/usr/bin"), None),
+            "icon_path" => add_browse_button(&grid, i as i32, entry, "Select Icon", None, Some(&icon_filter)),
+            _ => {}
+        }
+    }
+
+    // "Open With" needs a field code in Exec, picked next to it rather than typed
+    let field_codes = ["f", "F", "u", "U"];
+    let field_code_dropdown = DropDown::from_strings(&["No files", "%f (a file)", "%F (files)", "%u (a URL)", "%U (URLs)"]);
+    field_code_dropdown.set_tooltip_text(Some("What the application is given to open"));
+    if let Some(exec_row) = entries.iter().position(|(field_name, _, _)| *field_name == "exec_path") {
+        grid.attach(&field_code_dropdown, 3, exec_row as i32, 1, 1);
+    }
+
+    // Type is one of the spec's types, so it's picked rather than typed
+    let type_names: Vec<String> = AppType::ALL.iter().map(|app_type| app_type.to_string()).collect();
+    let type_dropdown = DropDown::from_strings(&type_names.iter().map(String::as_str).collect::<Vec<_>>());
+    let type_label = Label::new(Some("Type:"));
+    type_label.set_halign(gtk::Align::End);
+    grid.attach(&type_label, 0, entries.len() as i32, 1, 1);
+    grid.attach(&type_dropdown, 1, entries.len() as i32, 1, 1);
+
+    // Link entries open a URL rather than running an executable, so the type decides which field is shown
+    let field_widgets = |name: &str| {
+        entries.iter()
+            .find(|(field_name, _, _)| *field_name == name)
+            .map(|(_, label, entry)| (label.clone(), entry.clone()))
+            .expect("GUI field missing")
+    };
+    let (exec_label, exec_entry) = field_widgets("exec_path");
+    let exec_field_code = field_code_dropdown.clone();
+    let (url_label, url_entry) = field_widgets("url");
+    url_label.set_visible(false);
+    url_entry.set_visible(false);
+    type_dropdown.connect_selected_notify(move |type_dropdown| {
+        let is_link = AppType::ALL.get(type_dropdown.selected() as usize) == Some(&AppType::Link);
+        exec_label.set_visible(!is_link);
+        exec_entry.set_visible(!is_link);
+        exec_field_code.set_visible(!is_link);
+        url_label.set_visible(is_link);
+        url_entry.set_visible(is_link);
+    });
+
+    // Categories are picked from the main freedesktop categories rather than typed, so they're always valid
+    let category_checks: Vec<(&str, CheckButton)> = categories::MAIN_CATEGORIES.iter()
+        .map(|category| (*category, CheckButton::with_label(category)))
+        .collect();
+    let category_box = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    for (_, check) in &category_checks {
+        category_box.append(check);
+    }
+    let category_popover = Popover::new();
+    category_popover.set_child(Some(&category_box));
+    let category_button = MenuButton::new();
+    category_button.set_label("None selected");
+    category_button.set_popover(Some(&category_popover));
+    for (_, check) in &category_checks {
+        let category_button = category_button.clone();
+        let category_checks = category_checks.clone();
+        check.connect_toggled(move |_| {
+            let selected = selected_categories(&category_checks);
+            category_button.set_label(if selected.is_empty() { "None selected" } else { &selected });
+        });
+    }
+
+    let categories_label = Label::new(Some("Categories:"));
+    categories_label.set_halign(gtk::Align::End);
+    grid.attach(&categories_label, 0, entries.len() as i32 + 1, 1, 1);
+    grid.attach(&category_button, 1, entries.len() as i32 + 1, 1, 1);
+
+    // Boolean keys are checkboxes, Terminal below the fields and the rest in a collapsed advanced section
+    let checks = [
+        ("terminal_app", CheckButton::with_label("Run in a terminal")),
+        ("startup_notify", CheckButton::with_label("Show startup notification")),
+        ("no_display", CheckButton::with_label("Hide from menus (NoDisplay)")),
+        ("dbus_activatable", CheckButton::with_label("Launch through D-Bus (DBusActivatable)")),
+        ("prefers_non_default_gpu", CheckButton::with_label("Prefer the dedicated graphics card (PrefersNonDefaultGPU)")),
+    ];
+
+    let advanced_box = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    for (field_name, check) in &checks {
+        if *field_name == "terminal_app" {
+            grid.attach(check, 1, entries.len() as i32 + 2, 1, 1);
+        } else {
+            advanced_box.append(check);
+        }
+    }
+
+    // Vendor extension keys are free-form, one key and value per row with a button for another row
+    let extra_keys_grid = Grid::builder().row_spacing(6).column_spacing(6).build();
+    let extra_key_rows: Rc<RefCell<Vec<(Entry, Entry)>>> = Rc::new(RefCell::new(Vec::new()));
+    let add_extra_key_row = {
+        let extra_keys_grid = extra_keys_grid.clone();
+        let extra_key_rows = Rc::clone(&extra_key_rows);
+        move || {
+            let key_entry = Entry::builder().placeholder_text("X-Vendor-Key").hexpand(true).build();
+            let value_entry = Entry::builder().placeholder_text("Value").hexpand(true).build();
+            let row = extra_key_rows.borrow().len() as i32;
+            extra_keys_grid.attach(&key_entry, 0, row, 1, 1);
+            extra_keys_grid.attach(&value_entry, 1, row, 1, 1);
+            extra_key_rows.borrow_mut().push((key_entry, value_entry));
+        }
+    };
+    add_extra_key_row();
+    let add_extra_key_button = Button::with_label("Add extension key");
+    add_extra_key_button.connect_clicked(move |_| add_extra_key_row());
+    advanced_box.append(&Label::new(Some("Extension keys:")));
+    advanced_box.append(&extra_keys_grid);
+    advanced_box.append(&add_extra_key_button);
+
+    let advanced = Expander::new(Some("Advanced"));
+    advanced.set_child(Some(&advanced_box));
+    grid.attach(&advanced, 0, entries.len() as i32 + 3, 4, 1);
+
+    let button_row = entries.len() as i32 + 4;
+    let button = Button::with_label("Generate");
+    grid.attach(&button, 0, button_row, 4, 1);
+    button.set_margin_top(12);
+    button.set_hexpand(true);
+
+    // Add the informational label below the button, naming where the entry is saved
+    let destination = match &state.lock().unwrap().edit_path {
+        Some(edit_path) => format!("This will save the changes back to {}", edit_path.display()),
+        None => format!("This will only create .desktop files within {}", applications_dir.display()),
+    };
+    let info_label = Label::new(Some(destination.as_str()));
+    info_label.set_margin_top(12);
+    info_label.set_wrap(true);
+    info_label.set_margin_start(6);
+    grid.attach(&info_label, 0, button_row + 1, 4, 1);  // Attach to the row after the button
+
+    // Fill the form in from the state, which is only non-empty when editing an existing file
+    {
+        let initial = state.lock().unwrap();
+        for (field_name, _, entry) in &entries {
+            let value = match *field_name {
+                "name" => &initial.name,
+                "generic_name" => &initial.generic_name,
+                "comment" => &initial.comment,
+                "exec_path" => &initial.exec_path,
+                "url" => &initial.url,
+                "icon_path" => &initial.icon_path,
+                "working_dir" => &initial.working_dir,
+                "keywords" => &initial.keywords,
+                "mime_type" => &initial.mime_type,
+                "startup_wm_class" => &initial.startup_wm_class,
+                _ => continue,
+            };
+            entry.set_text(value);
+        }
+        for (field_name, check) in &checks {
+            let active = match *field_name {
+                "terminal_app" => initial.terminal_app == "true",
+                "startup_notify" => initial.startup_notify == "true",
+                "no_display" => initial.no_display,
+                "dbus_activatable" => initial.dbus_activatable,
+                "prefers_non_default_gpu" => initial.prefers_non_default_gpu,
+                _ => continue,
+            };
+            check.set_active(active);
+        }
+        let initial_type = AppType::from_str(&initial.app_type).unwrap_or_default();
+        let type_index = AppType::ALL.iter().position(|app_type| *app_type == initial_type).unwrap_or_default();
+        type_dropdown.set_selected(type_index as u32);
+        for (category, check) in &category_checks {
+            check.set_active(initial.categories.split(';').any(|selected| selected == *category));
+        }
+    }
+
+    // Create the window
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title("Desktop Entry Generator")
+        .child(&grid)
+        .default_width(400)
+        .build();
+
+    // Create clones for the closure
+    let entries_clone = entries.to_vec();
+    // Category checkboxes are cleared along with the others once the file is saved
+    let checks_clone: Vec<(&str, CheckButton)> = checks.iter().chain(category_checks.iter()).cloned().collect();
+    let category_checks_clone = category_checks.clone();
+    let field_code_clone = field_code_dropdown.clone();
+    let type_clone = type_dropdown.clone();
+    let extra_key_rows_clone = Rc::clone(&extra_key_rows);
+    let state_clone = Arc::clone(state);
+    let window_clone = window.clone();
+    let applications_dir = applications_dir.to_path_buf();
+
+    // Handle button click
+    button.connect_clicked(move |_| {
+        let mut state = state_clone.lock().unwrap();
+
+        // Update state with values from entries
+        for (field_name, _, entry) in &entries_clone {
+            let value = entry.text().to_string();
+            match *field_name {
+                "name" => state.name = value,
+                "generic_name" => state.generic_name = value,
+                "comment" => state.comment = value,
+                "exec_path" => state.exec_path = value,
+                "url" => state.url = value,
+                "icon_path" => state.icon_path = value,
+                "working_dir" => state.working_dir = value,
+                "keywords" => state.keywords = value,
+                "mime_type" => state.mime_type = value,
+                "startup_wm_class" => state.startup_wm_class = value,
+                _ => {}
+            }
+        }
+
+        for (field_name, check) in &checks_clone {
+            let value = if check.is_active() { String::from("true") } else { String::new() };
+            match *field_name {
+                "terminal_app" => state.terminal_app = check.is_active().to_string(),
+                "startup_notify" => state.startup_notify = value,
+                "no_display" => state.no_display = check.is_active(),
+                "dbus_activatable" => state.dbus_activatable = check.is_active(),
+                "prefers_non_default_gpu" => state.prefers_non_default_gpu = check.is_active(),
+                _ => {}
+            }
+        }
+
+        state.categories = selected_categories(&category_checks_clone);
+        state.extra_keys = extra_key_rows_clone.borrow().iter()
+            .map(|(key, value)| (key.text().to_string(), value.text().to_string()))
+            .filter(|(key, _)| !key.trim().is_empty())
+            .collect();
+        let app_type = AppType::ALL.get(type_clone.selected() as usize).copied().unwrap_or_default();
+        state.app_type = app_type.to_string();
+        // The first choice is no field code, the rest follow the order of field_codes
+        state.field_code = match field_code_clone.selected() {
+            0 => String::new(),
+            selected => field_codes.get(selected as usize - 1).map(|code| code.to_string()).unwrap_or_default(),
+        };
+
+        state.keywords = match desktop_entry::normalize_keywords(&state.keywords) {
+            Ok(keywords) => keywords,
+            Err(e) => {
+                show_error_dialog(&window_clone, &e.to_string());
+                return;
+            }
+        };
+
+        state.mime_type = match desktop_entry::normalize_mime_types(&state.mime_type) {
+            Ok(mime_type) => mime_type,
+            Err(e) => {
+                show_error_dialog(&window_clone, &e.to_string());
+                return;
+            }
+        };
+
+        let startup_notify = match desktop_entry::parse_startup_notify(&state.startup_notify) {
+            Ok(startup_notify) => startup_notify,
+            Err(e) => {
+                show_error_dialog(&window_clone, &e.to_string());
+                return;
+            }
+        };
+
+        // Create and save the desktop entry, a missing Name is shown like any other error of the build
+        // An edited entry only gets what was changed in the form, so the keys the form doesn't show are kept
+        let builder = match &state.edit_base {
+            Some(edit_base) => edited_entry(edit_base, &state, app_type, startup_notify),
+            None => DesktopEntry::builder()
+                .name(state.name.clone())
+                .generic_name(state.generic_name.clone())
+                .comment(state.comment.clone())
+                .exec(state.exec_path.clone())
+                .field_code(state.field_code.clone())
+                .icon(state.icon_path.clone())
+                .terminal(state.terminal_app == "true")
+                .app_type(app_type)
+                .url(if app_type == AppType::Link { state.url.clone() } else { String::new() })
+                .categories(categories::parse_categories(&state.categories))
+                .keywords(state.keywords.clone())
+                .mime_type(state.mime_type.clone())
+                .startup_notify(startup_notify)
+                .startup_wm_class(state.startup_wm_class.clone())
+                .no_display(state.no_display)
+                .working_dir(state.working_dir.clone())
+                .dbus_activatable(state.dbus_activatable)
+                .prefers_non_default_gpu(state.prefers_non_default_gpu),
+        };
+        let entry = state.extra_keys.iter()
+            .fold(builder, |builder, (key, value)| builder.extension_key(key.clone(), value.clone()))
+            .build();
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                show_error_dialog(&window_clone, &e.to_string());
+                return;
+            }
+        };
+
+        // An edited file is saved back over itself
+        let path = match &state.edit_path {
+            Some(edit_path) => edit_path.clone(),
+            None => applications_dir.join(format!("{}.desktop", desktop_entry::slugify(&state.name))),
+        };
+
+        // Errors would make a broken file, so they're shown instead of saving
+        let errors: Vec<String> = entry.validate()
+            .into_iter()
+            .filter(|issue| issue.severity == desktop_entry::Severity::Error)
+            .map(|issue| issue.to_string())
+            .collect();
+        if !errors.is_empty() {
+            show_error_dialog(&window_clone, &errors.join("\n"));
+            return;
+        }
+        // Warnings (ex: a missing icon file) don't stop the save, they're listed in the confirmation
+        let mut notes: Vec<String> = entry.warnings()
+            .into_iter()
+            .map(|warning| format!("Warning: {}", warning))
+            .collect();
+        if let Some(icon) = entry.value("Icon")
+            && desktop_entry::check_icon(&icon) == desktop_entry::IconCheck::ThemeName {
+            notes.push(format!("Icon {} will be looked up in the icon theme", icon));
+        }
+
+        let entry = if state.no_header { entry } else { entry.with_generated_header() };
+        // The lines of an edited file that weren't changed are saved as they were
+        let contents = match state.edit_path.as_ref().and_then(|edit_path| fs::read_to_string(edit_path).ok()) {
+            Some(original) => entry.to_string_keeping(&original),
+            None => entry.to_string(),
+        };
+
+        // Ask before replacing an existing entry, it may have been edited by hand
+        if path.exists() && state.edit_path.is_none() {
+            let dialog = gtk::MessageDialog::new(
+                Some(&window_clone),
+                gtk::DialogFlags::MODAL,
+                gtk::MessageType::Question,
+                gtk::ButtonsType::YesNo,
+                format!("{} already exists, overwrite it?", path.to_str().unwrap_or(""))
+            );
+
+            let window = window_clone.clone();
+            let entries = entries_clone.clone();
+            let checks = checks_clone.clone();
+            let saved_state = Arc::clone(&state_clone);
+            dialog.connect_response(move |dialog, response| {
+                dialog.close();
+                if response == gtk::ResponseType::Yes && save_entry(&window, &path, &contents, &notes, &entries, &checks) {
+                    saved_state.lock().unwrap().saved = true;
+                }
+            });
+
+            dialog.show();
+        } else if save_entry(&window_clone, &path, &contents, &notes, &entries_clone, &checks_clone) {
+            state.saved = true;
+        }
+    });
+
+    window.present();
+
+}
+
+/// Applies the form over the entry being edited, setting only the fields that differ from what was
+/// loaded: a Terminal or StartupNotify key the file didn't have stays out, and categories the form
+/// has no checkbox for are kept alongside the main categories picked
+pub(crate) fn edited_entry(base: &DesktopEntry, state: &AppState, app_type: AppType, startup_notify: Option<bool>) -> DesktopEntryBuilder {
+    let loaded = AppState::from_entry(base.clone(), PathBuf::new());
+    let mut builder = DesktopEntryBuilder::from(base.clone());
+    let normalized = |normalize: fn(&str) -> Result<String, CreateDesktopError>, value: &str| normalize(value).unwrap_or_else(|_| value.to_string());
+
+    if state.name != loaded.name {
+        builder = builder.name(state.name.clone());
+    }
+    if state.generic_name != loaded.generic_name {
+        builder = builder.generic_name(state.generic_name.clone());
+    }
+    if state.comment != loaded.comment {
+        builder = builder.comment(state.comment.clone());
+    }
+    if state.exec_path != loaded.exec_path {
+        builder = builder.exec(state.exec_path.clone());
+    }
+    if !state.field_code.is_empty() {
+        builder = builder.field_code(state.field_code.clone());
+    }
+    if state.icon_path != loaded.icon_path {
+        builder = builder.icon(state.icon_path.clone());
+    }
+    if state.working_dir != loaded.working_dir {
+        builder = builder.working_dir(state.working_dir.clone());
+    }
+    if (state.terminal_app == "true") != (loaded.terminal_app == "true") {
+        builder = builder.terminal(state.terminal_app == "true");
+    }
+    if AppType::from_str(&loaded.app_type).unwrap_or_default() != app_type {
+        builder = builder.app_type(app_type);
+    }
+    if app_type == AppType::Link && state.url != loaded.url {
+        builder = builder.url(state.url.clone());
+    } else if app_type != AppType::Link && !loaded.url.is_empty() {
+        builder = builder.url("");
+    }
+
+    let (mut loaded_main, kept): (Vec<String>, Vec<String>) = categories::parse_categories(&loaded.categories)
+        .into_iter()
+        .partition(|category| categories::MAIN_CATEGORIES.contains(&category.as_str()));
+    let mut selected = categories::parse_categories(&state.categories);
+    let picked = selected.clone();
+    loaded_main.sort();
+    selected.sort();
+    if selected != loaded_main {
+        builder = builder.categories(picked.into_iter().chain(kept));
+    }
+
+    if state.keywords != normalized(desktop_entry::normalize_keywords, &loaded.keywords) {
+        builder = builder.keywords(state.keywords.clone());
+    }
+    if state.mime_type != normalized(desktop_entry::normalize_mime_types, &loaded.mime_type) {
+        builder = builder.mime_type(state.mime_type.clone());
+    }
+    if (state.startup_notify == "true") != (loaded.startup_notify == "true") {
+        builder = builder.startup_notify(startup_notify);
+    }
+    if state.startup_wm_class != loaded.startup_wm_class {
+        builder = builder.startup_wm_class(state.startup_wm_class.clone());
+    }
+    if state.no_display != loaded.no_display {
+        builder = builder.no_display(state.no_display);
+    }
+    if state.dbus_activatable != loaded.dbus_activatable {
+        builder = builder.dbus_activatable(state.dbus_activatable);
+    }
+    if state.prefers_non_default_gpu != loaded.prefers_non_default_gpu {
+        builder = builder.prefers_non_default_gpu(state.prefers_non_default_gpu);
+    }
+    builder
+}
+
+/// Writes the entry to `path`, then confirms it to the user and clears the form. Returns whether it was written
+fn save_entry(window: &ApplicationWindow, path: &Path, contents: &str, notes: &[String], entries: &[(&str, Label, Entry)], checks: &[(&str, CheckButton)]) -> bool {
+    // Create directory if it doesn't exist
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).unwrap_or_else(|e| {
+            eprintln!("Failed to create directory: {}", e);
+        });
+    }
+
+    // Write the file
+    if let Ok(mut file) = File::create(path)
+        && file.write_all(contents.as_bytes()).is_ok() {
+        // Show success message
+        let mut message = format!("Successfully created .desktop file at:\n{}", path.to_str().unwrap_or(""));
+        if !notes.is_empty() {
+            message.push_str("\n\n");
+            message.push_str(&notes.join("\n"));
+        }
+        let dialog = gtk::MessageDialog::new(
+            Some(window),
+            gtk::DialogFlags::MODAL,
+            gtk::MessageType::Info,
+            gtk::ButtonsType::Ok,
+            message
+        );
+
+        dialog.connect_response(|dialog, _| {
+            dialog.close();
+        });
+
+        dialog.show();
+
+        // Clear all entry fields
+        for (_, _, entry) in entries {
+            entry.set_text("");
+        }
+        for (_, check) in checks {
+            check.set_active(false);
+        }
+        return true;
+    }
+    show_error_dialog(window, &format!("Failed to write {}", path.display()));
+    false
+}
+
+/// Adds a "Browse…" button next to `entry` that fills it with the path picked in a file chooser
+fn add_browse_button(grid: &Grid, row: i32, entry: &Entry, title: &'static str, folder: Option<&str>, filter: Option<&FileFilter>) {
+    let browse = Button::with_label("Browse…");
+    grid.attach(&browse, 2, row, 1, 1);
+
+    let entry = entry.clone();
+    let folder = folder.map(gtk::gio::File::for_path);
+    let filter = filter.cloned();
+    browse.connect_clicked(move |browse| {
+        let parent = browse.root().and_downcast::<gtk::Window>();
+        let dialog = FileChooserDialog::new(
+            Some(title),
+            parent.as_ref(),
+            FileChooserAction::Open,
+            &[("Cancel", gtk::ResponseType::Cancel), ("Open", gtk::ResponseType::Accept)],
+        );
+        if let Some(folder) = &folder
+            && let Err(e) = dialog.set_current_folder(Some(folder)) {
+            eprintln!("Failed to open {}: {}", folder.parse_name(), e);
+        }
+        if let Some(filter) = &filter {
+            dialog.add_filter(filter);
+        }
+
+        let entry = entry.clone();
+        dialog.connect_response(move |dialog, response| {
+            if response == gtk::ResponseType::Accept
+                && let Some(path) = dialog.file().and_then(|file| file.path()) {
+                entry.set_text(&path.to_string_lossy());
+            }
+            dialog.close();
+        });
+
+        dialog.show();
+    });
+}
+
+/// Joins the ticked categories into a Categories value (ex: Development;Utility;)
+fn selected_categories(category_checks: &[(&str, CheckButton)]) -> String {
+    category_checks.iter()
+        .filter(|(_, check)| check.is_active())
+        .map(|(category, _)| format!("{};", category))
+        .collect()
+}
+
+fn show_error_dialog(window: &ApplicationWindow, message: &str) {
+    let dialog = gtk::MessageDialog::new(
+        Some(window),
+        gtk::DialogFlags::MODAL,
+        gtk::MessageType::Error,
+        gtk::ButtonsType::Ok,
+        message,
+    );
+
+    dialog.connect_response(|dialog, _| {
+        dialog.close();
+    });
+
+    dialog.show();
+}
@@ -1,30 +1,39 @@
-use crate::flags;
-pub fn display_help_information(args: Vec<String>) {
-    println!("create-desktop-file v{}", env!("CARGO_PKG_VERSION"));
-    println!("create-desktop-file is a simple tool to create .desktop files for Linux. \n\
-    By default it will open a GUI app, however this can also run in Terminal by specifying the below flags/arguments");
-    println!("Usage: {}  [--global | --local] etc.", args[0]);
-    println!("Options:");
-    println!("  {}", flags::LOCAL);
-    println!("      Install .desktop file locally in ~/.local/share/applications/");
-    println!("  {}", flags::GLOBAL);
-    println!("      Install .desktop file globally in /usr/share/applications/");
-    println!("  {}", flags::NAME);
-    println!("      Set the name of the .desktop file, if not used, it will ask you specifically for the details");
-    println!("  {}", flags::COMMENT);
-    println!("      (Requires --name) Set the comment of the .desktop file");
-    println!("  {}", flags::EXEC_PATH);
-    println!("      (Requires --name) Set the command to execute");
-    println!("  {}", flags::ICON_PATH);
-    println!("      (Requires --name) Set the icon to use");
-    println!("  {}", flags::TERMINAL_APP);
-    println!("      (Requires --name) Run the command in Terminal");
-    println!("  {}", flags::APP_TYPE);
-    println!("      (Requires --name) Set the type of the .desktop file (Application, Link, Directory, etc.)");
-    println!("  {}", flags::CATEGORIES);
-    println!("      (Requires --name) Set the categories of the .desktop file (Utility, Game, etc.)");
-    println!("  {}", flags::VERSION);
-    println!("      Show version information");
-    println!("  {}", flags::HELP);
-    println!("      Show this help message");
-}
\ No newline at end of file
+use std::path::Path;
+use crate::app_error;
+use crate::cli::CliArgs;
+
+pub fn display_help_information(args: &[String]) {
+    print!("{}", help_information(args));
+}
+
+/// The --help message, the options and a few examples, all naming the program the way it was run (ex: cdf)
+pub fn help_information(args: &[String]) -> String {
+    let program = program_name(args);
+    // The options are listed from the definitions the arguments are parsed with
+    let mut help = format!("create-desktop-file v{}\n", env!("CARGO_PKG_VERSION"));
+    help.push_str(&CliArgs::help(&program));
+    help.push_str(&format!(
+        "\nExamples:\n\
+        \x20 Create ~/.local/share/applications/my-app.desktop:\n\
+        \x20   {program} --name \"My App\" --exec-path /usr/bin/my-app --icon-path my-app --categories Utility --comment Does my app things\n\
+        \x20 Install into /usr/share/applications for every user:\n\
+        \x20   sudo {program} --global --name \"My App\" --exec-path /usr/bin/my-app\n\
+        \x20 Answer the questions in the terminal instead:\n\
+        \x20   {program} --interactive\n\
+        \x20 The same first example with the short flags, -c is --comment and -C (capital) is --categories:\n\
+        \x20   {program} -n \"My App\" -e /usr/bin/my-app -i my-app -C Utility -c Does my app things\n"
+    ));
+    help.push_str("\nExit status:\n");
+    for (code, meaning) in app_error::EXIT_CODES {
+        help.push_str(&format!("  {:<3} {}\n", code, meaning));
+    }
+    help
+}
+
+/// The file name of args[0], without the directory it was run from, "create-desktop-file" without one
+pub fn program_name(args: &[String]) -> String {
+    args.first()
+        .and_then(|program| Path::new(program).file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "create-desktop-file".to_string())
+}
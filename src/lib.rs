@@ -0,0 +1,37 @@
+//! Building, parsing and writing freedesktop.org desktop entries (.desktop files).
+//!
+//! ```no_run
+//! use create_desktop_file::{write_entry, DesktopEntry};
+//!
+//! let entry = DesktopEntry::builder()
+//!     .name("Foo")
+//!     .exec("/usr/bin/foo")
+//!     .categories(["Utility"])
+//!     .build()?;
+//! let path = write_entry(&entry, std::path::Path::new("/tmp/applications"))?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! The create-desktop-file binary is built on top of this, its GTK form is behind the `gui`
+//! feature so depending on the library alone doesn't pull in GTK.
+pub mod categories;
+pub mod desktop_entry;
+pub mod desktop_utils;
+pub mod error;
+pub mod path;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub use desktop_entry::{AppType, DesktopAction, DesktopEntry, DesktopEntryBuilder, DesktopEntrySpec, FieldChange, Severity, ValidationIssue};
+pub use error::{CreateDesktopError, ParseError};
+
+/// Writes `entry` to `dir`, named after its slugified Name (see [`DesktopEntry::file_name`]),
+/// creating `dir` when needed, and returns the path. An existing file is replaced.
+pub fn write_entry(entry: &DesktopEntry, dir: &Path) -> io::Result<PathBuf> {
+    let path = dir.join(entry.file_name());
+    fs::create_dir_all(dir)?;
+    fs::write(&path, entry.to_string())?;
+    Ok(path)
+}
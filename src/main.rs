@@ -1,66 +1,173 @@
 //! Contains modules and components required for desktop entry generation.
-mod desktop_entry;
-mod user_details;
+//! The entry itself is handled by the create_desktop_file library, this is the command line
+//! and the GTK form around it.
+mod app_error;
+mod cli;
+mod config;
 mod flags;
+mod user_details;
 mod help_information;
-mod path;
 mod desktop_entry_tests;
 mod modes;
+mod manage;
+#[cfg(feature = "gui")]
+mod gui;
 
-use std::fs::File;
-use std::io::Write;
 use std::{env};
-use gtk;
-use gtk::prelude::*;
-use gtk::{Application, ApplicationWindow, Button, Entry, Grid, Label};
-use std::sync::{Arc, Mutex};
+use std::path::Path;
+#[cfg(feature = "gui")]
+use std::path::PathBuf;
+use create_desktop_file::{categories, desktop_entry, desktop_utils, error, path};
+use cli::CliArgs;
+#[cfg(feature = "gui")]
+use desktop_entry::DesktopEntry;
+use app_error::AppError;
 
 #[derive(Default)]
 struct AppState {
     name: String,
+    generic_name: String,
     comment: String,
     exec_path: String,
+    try_exec: String,
+    field_code: String,
     icon_path: String,
     terminal_app: String,
     app_type: String,
     categories: String,
+    keywords: String,
+    mime_type: String,
+    startup_notify: String,
+    startup_wm_class: String,
+    no_display: bool,
+    hidden: bool,
+    working_dir: String,
+    actions: Vec<String>,
+    dbus_activatable: bool,
+    url: String,
+    only_show_in: String,
+    not_show_in: String,
+    prefers_non_default_gpu: bool,
+    spec_version: String,
+    no_spec_version: bool,
+    localized_names: Vec<(String, String)>,
+    localized_generic_names: Vec<(String, String)>,
+    localized_comments: Vec<(String, String)>,
+    extra_keys: Vec<(String, String)>,
+    // Set when editing an existing file, so the GUI saves back over it and keeps the keys the form doesn't show
+    #[cfg(feature = "gui")]
+    edit_path: Option<PathBuf>,
+    #[cfg(feature = "gui")]
+    edit_base: Option<DesktopEntry>,
+    // --no-header, leaves the "Created by" comment out of the saved file
+    #[cfg(feature = "gui")]
+    no_header: bool,
+    // Set once the form has saved an entry, so the desktop database is only refreshed after a save
+    #[cfg(feature = "gui")]
+    saved: bool,
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(e.exit_code());
+    }
 }
 
-fn main() -> std::io::Result<()> {
+fn run() -> Result<(), AppError> {
 
     // Flags supported by the application
 
     //Supported OSes
     let supported_oses: Vec<&str> = vec!["linux"];
     let os: &str = env::consts::OS;
-    break_here_if_os_not_supported(supported_oses, &os);
+    break_here_if_os_not_supported(supported_oses, &os)?;
 
     // Get all arguments
-    let args: Vec<String> = env::args().collect();
-    
-    // Check for CLI flags
-    let is_cli = args.iter().any(|arg| arg == flags::LOCAL || arg == flags::GLOBAL || arg == flags::NAME);
-    let is_global = args.iter().any(|arg| arg == flags::GLOBAL);
-
+    let raw_args: Vec<String> = env::args().collect();
+    let mut args = CliArgs::from_args(&raw_args)?;
+    for flag in &args.ignored_flags {
+        eprintln!("Warning: {}", flag);
+    }
 
     // Check if user wants to view help information first
-    if args.iter().any(|arg| arg == flags::HELP) {
-        help_information::display_help_information(args);
+    if args.help {
+        help_information::display_help_information(&raw_args);
         std::process::exit(0);
     }
     
     // Check if user wants to view version
-    if args.iter().any(|arg: &String| arg == flags::VERSION) {
+    if args.version {
         println!("CreateDesktopFile v{}", env!("CARGO_PKG_VERSION"));
         std::process::exit(0);
     }
     
+    // The config file is only read now, so --help and --version work with a broken one
+    let default_config = config::default_path();
+    args.load_defaults(default_config.as_deref())?;
+
+    // Check for CLI flags
+    let is_cli = args.local
+        || args.global
+        || args.name.is_some()
+        || args.output.is_some()
+        || args.stdout
+        || args.dry_run
+        || args.interactive
+        || args.from_json.is_some()
+        || args.from_toml.is_some()
+        || args.export_json
+        || args.stdin
+        // --edit on its own opens the GUI on the file, with other details it edits from the CLI
+        || (args.edit.is_some() && args.has_details());
+    let is_global = args.is_global();
+    let global_share_applications = &args.global_dir(path::GLOBAL_SHARE_APPLICATIONS);
+
+
+    // Check if user wants a completion script for their shell, completing the name they ran it as (ex: cdf)
+    if let Some(shell) = args.generate_completions {
+        print!("{}", CliArgs::completions(shell, &help_information::program_name(&raw_args)));
+        return Ok(());
+    }
+
+    // Check if user wants to list the installed entries
+    if args.list {
+        manage::run_list(is_global, args.all, path::LOCAL_SHARE_APPLICATIONS, global_share_applications)?;
+        return Ok(());
+    }
+
+    // Check if user wants to delete an installed entry
+    if let Some(name) = &args.delete {
+        manage::run_delete(is_global, name, args.yes, path::LOCAL_SHARE_APPLICATIONS, global_share_applications)?;
+        return Ok(());
+    }
+
+    // Check if user wants to print an installed entry, ex: to move it into a --from-json file
+    if let Some(name) = &args.export {
+        manage::run_export(is_global, name, args.json, path::LOCAL_SHARE_APPLICATIONS, global_share_applications)?;
+        return Ok(());
+    }
+
+    // Check if user wants to see where an installed entry is and what's in it
+    if let Some(name) = &args.show {
+        manage::run_show(name, args.parsed, path::LOCAL_SHARE_APPLICATIONS, global_share_applications)?;
+        return Ok(());
+    }
+
+    // Check if user wants to lint existing files, ex: in CI
+    if let Some(paths) = args.files_to_validate() {
+        if manage::run_validate_files(paths)? > 0 {
+            std::process::exit(app_error::EXIT_FAILURE);
+        }
+        return Ok(());
+    }
+
     if is_cli {
         // Run CLI version
-        modes::run_cli(is_global, args, path::LOCAL_SHARE_APPLICATIONS, path::GLOBAL_SHARE_APPLICATIONS)?;
+        // run_cli moves the global directory under --prefix itself
+        modes::run_cli(is_global, raw_args, path::LOCAL_SHARE_APPLICATIONS, path::GLOBAL_SHARE_APPLICATIONS, default_config.as_deref())?;
     } else {
-        // Run GUI version
-        modes::run_gui(path::LOCAL_SHARE_APPLICATIONS)?;
+        run_gui(&args, raw_args, default_config.as_deref())?;
     }
 
     Ok(())
@@ -68,145 +175,29 @@ fn main() -> std::io::Result<()> {
 
 
 
-fn break_here_if_os_not_supported(supported_oses: Vec<&str>, os: &&str) {
-    if !supported_oses.contains(&os) {
+fn break_here_if_os_not_supported(supported_oses: Vec<&str>, os: &&str) -> Result<(), AppError> {
+    if !supported_oses.contains(os) {
         println!("--------------------------------------------------------------------");
         println!("This progam is only supported by the following Operating Systems:");
         println!("--------------------------------------------------------------------");
         supported_oses.iter().for_each(|os| println!("{}", os));
         println!("--------------------------------------------------------------------");
-        panic!("This program is not running on a supported OS. Exiting.");
+        return Err(AppError::UnsupportedOs(os.to_string()));
     }
+    Ok(())
 }
 
-fn build_ui(app: &Application, state: &Arc<Mutex<AppState>>) {
-    let grid = Grid::builder()
-        .margin_start(12)
-        .margin_end(12)
-        .margin_top(12)
-        .margin_bottom(12)
-        .row_spacing(6)
-        .column_spacing(12)
-        .build();
-
-    // Create labels and entries with their corresponding field names
-    let entries = [
-        ("name", Label::new(Some("Name:")), Entry::new()),
-        ("comment", Label::new(Some("Comment:")), Entry::new()),
-        ("exec_path", Label::new(Some("Executable Path:")), Entry::new()),
-        ("icon_path", Label::new(Some("Icon Path:")), Entry::new()),
-        ("terminal_app", Label::new(Some("Terminal App?:")), Entry::new()),
-        ("categories", Label::new(Some("Categories:")), Entry::new()),
-        ("type", Label::new(Some("Type:")), Entry::new()),
-    ];
-
-
-    // Add labels and entries to the grid
-    for (i, (_, label, entry)) in entries.iter().enumerate() {
-        label.set_halign(gtk::Align::End);
-        grid.attach(label, 0, i as i32, 1, 1);
-        grid.attach(entry, 1, i as i32, 1, 1);
-        entry.set_hexpand(true);
-    }
-
-    let button = Button::with_label("Generate");
-    grid.attach(&button, 0, 7, 2, 1);
-    button.set_margin_top(12);
-    button.set_hexpand(true);
-
-    // Add the informational label below the button
-    let info_label = Label::new(Some(format!("This will only create .desktop files within ~/{}", path::LOCAL_SHARE_APPLICATIONS).as_str()));
-    info_label.set_margin_top(12);
-    info_label.set_wrap(true);
-    info_label.set_margin_start(6);
-    grid.attach(&info_label, 0, 8, 2, 1);  // Attach to row 7 (after the button which is at row 6)
-
-    // Create the window
-    let window = ApplicationWindow::builder()
-        .application(app)
-        .title("Desktop Entry Generator")
-        .child(&grid)
-        .default_width(400)
-        .build();
-
-    // Create clones for the closure
-    let entries_clone = entries.to_vec();
-    let state_clone = Arc::clone(&state);
-    let window_clone = window.clone();
-
-    // Handle button click
-    button.connect_clicked(move |_| {
-        let mut state = state_clone.lock().unwrap();
-
-        // Update state with values from entries
-        for (field_name, _, entry) in &entries_clone {
-            let value = entry.text().to_string();
-            match *field_name {
-                "name" => state.name = value,
-                "comment" => state.comment = value,
-                "exec_path" => state.exec_path = value,
-                "icon_path" => state.icon_path = value,
-                "terminal_app" => state.terminal_app = value,
-                "categories" => state.categories = value,
-                "type" => state.app_type = value,
-                _ => {}
-            }
-        }
-
-        // Create and save the desktop entry
-        if !state.name.is_empty() {
-            let mut path = dirs::home_dir()
-                .expect("Failed to get home directory");
-
-            path.push(path::LOCAL_SHARE_APPLICATIONS);
-            path.push(format!("{}.desktop", state.name.trim()));
-
-            // Create the desktop entry
-            let entry = desktop_entry::DesktopEntry::new(
-                state.name.clone(),
-                state.comment.clone(),
-                state.exec_path.clone(),
-                state.icon_path.clone(),
-                state.terminal_app.clone(),
-                String::from("Application"), // Default app_type
-                state.categories.clone(),
-            );
-
-            // Create directory if it doesn't exist
-            if let Some(parent) = path.parent() {
-                std::fs::create_dir_all(parent).unwrap_or_else(|e| {
-                    eprintln!("Failed to create directory: {}", e);
-                });
-            }
-
-            // Write the file
-            if let Ok(mut file) = File::create(&path) {
-                if let Ok(_) = file.write_all(entry.to_string().as_bytes()) {
-                    // Show success message
-                    let dialog = gtk::MessageDialog::new(
-                        Some(&window_clone),
-                        gtk::DialogFlags::MODAL,
-                        gtk::MessageType::Info,
-                        gtk::ButtonsType::Ok,
-                        &format!("Successfully created .desktop file at:\n{}",
-                                 path.to_str().unwrap_or(""))
-                    );
-
-                    dialog.connect_response(|dialog, _| {
-                        dialog.close();
-                    });
-
-                    dialog.show();
-
-                    // Clear all entry fields
-                    for (_, _, entry) in &entries_clone {
-                        entry.set_text("");
-                    }
-                }
-            }
-        }
-    });
-
-    window.present();
+/// Opens the GTK form
+#[cfg(feature = "gui")]
+fn run_gui(args: &CliArgs, _raw_args: Vec<String>, _default_config: Option<&Path>) -> Result<(), AppError> {
+    let edit_path = args.edit.as_deref()
+        .map(|target| manage::locate_entry(target, args.is_global(), path::LOCAL_SHARE_APPLICATIONS, &args.global_dir(path::GLOBAL_SHARE_APPLICATIONS)))
+        .transpose()?;
+    gui::run_gui(path::LOCAL_SHARE_APPLICATIONS, edit_path, args.refreshes(false), args.no_header, &args.defaults)
+}
 
-}
\ No newline at end of file
+/// Built without the GTK form, so the details are asked for in the terminal instead
+#[cfg(not(feature = "gui"))]
+fn run_gui(args: &CliArgs, raw_args: Vec<String>, default_config: Option<&Path>) -> Result<(), AppError> {
+    modes::run_cli(args.is_global(), raw_args, path::LOCAL_SHARE_APPLICATIONS, path::GLOBAL_SHARE_APPLICATIONS, default_config)
+}
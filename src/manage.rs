@@ -0,0 +1,376 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use crate::desktop_entry;
+use crate::desktop_entry::{DesktopEntry, Severity};
+use crate::app_error::AppError;
+use crate::modes;
+
+/// A line of --list, an installed file with its Name and Exec
+#[derive(Debug, PartialEq)]
+pub struct ListedEntry {
+    pub file_name: String,
+    pub name: String,
+    pub exec: String,
+    /// Whether the program Exec starts is installed, `None` when there's no Exec (ex: a Link entry)
+    pub exec_found: Option<bool>,
+}
+
+/// Prints every entry installed locally, or globally with `is_global`, or in both directories with `all`
+pub fn run_list(is_global: bool, all: bool, local_share_applications: &str, global_share_applications: &str) -> Result<(), AppError> {
+    let locations = if all { vec![false, true] } else { vec![is_global] };
+    for (i, is_global) in locations.into_iter().enumerate() {
+        // Listing only reads, so a global listing doesn't need root
+        let dir = modes::applications_path(is_global, local_share_applications, global_share_applications)?;
+        if all {
+            if i > 0 {
+                println!();
+            }
+            println!("{}:", dir.display());
+        }
+        print_listing(&dir, &listed_entries(&dir)?);
+    }
+    Ok(())
+}
+
+/// Prints the entries of `dir` in aligned columns
+fn print_listing(dir: &Path, entries: &[ListedEntry]) {
+    if entries.is_empty() {
+        println!("No entries found in {}", dir.display());
+        return;
+    }
+
+    let width = |header: &str, column: fn(&ListedEntry) -> &str| {
+        entries.iter().map(|entry| column(entry).chars().count()).max().unwrap_or(0).max(header.len())
+    };
+    let file_width = width("FILE", |entry| &entry.file_name);
+    let name_width = width("NAME", |entry| &entry.name);
+    let exec_width = width("EXEC", |entry| &entry.exec);
+    println!("{:<file_width$}  {:<name_width$}  {:<exec_width$}  INSTALLED", "FILE", "NAME", "EXEC");
+    for entry in entries {
+        let installed = match entry.exec_found {
+            Some(true) => "yes",
+            Some(false) => "no",
+            None => "-",
+        };
+        println!("{:<file_width$}  {:<name_width$}  {:<exec_width$}  {}", entry.file_name, entry.name, entry.exec, installed);
+    }
+}
+
+/// The entries installed in `dir` for --list. One that doesn't parse is still listed, with
+/// a warning and only the Name line that could be found
+pub fn listed_entries(dir: &Path) -> io::Result<Vec<ListedEntry>> {
+    let mut listed = Vec::new();
+    for (name, path) in list_entries(dir)? {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                eprintln!("Warning: {} couldn't be read, skipping it: {}", path.display(), e);
+                continue;
+            }
+        };
+        let listed_entry = match DesktopEntry::from_str(&contents) {
+            Ok(entry) => {
+                let exec = entry.value("Exec");
+                ListedEntry {
+                    file_name,
+                    name,
+                    exec_found: exec.as_deref().map(|exec| desktop_entry::verify_exec(exec).is_none()),
+                    exec: exec.unwrap_or_default(),
+                }
+            }
+            Err(e) => {
+                eprintln!("Warning: {} couldn't be parsed: {}", path.display(), e);
+                ListedEntry { file_name, name, exec: String::new(), exec_found: None }
+            }
+        };
+        listed.push(listed_entry);
+    }
+    Ok(listed)
+}
+
+/// Deletes the `<name>.desktop` entry installed locally, or globally with `is_global` (which needs root),
+/// or the .desktop file at the path `name`. Its Name and Exec are shown first and, unless `assume_yes`,
+/// the deletion has to be confirmed
+pub fn run_delete(is_global: bool, name: &str, assume_yes: bool, local_share_applications: &str, global_share_applications: &str) -> Result<(), AppError> {
+    let target = Path::new(name);
+    let path = if target.is_file() {
+        target.to_path_buf()
+    } else {
+        let dir = modes::applications_dir(is_global, local_share_applications, global_share_applications)?;
+        entry_path(&dir, name.trim_end_matches(".desktop"))
+    };
+    if !path.is_file() {
+        return Err(AppError::EntryNotFound(path.display().to_string()));
+    }
+
+    // A file that doesn't parse can still be deleted, there's just nothing to show for it
+    if let Ok(entry) = DesktopEntry::from_str(&fs::read_to_string(&path)?) {
+        for key in ["Name", "Exec"] {
+            if let Some(value) = entry.value(key) {
+                println!("{}={}", key, value);
+            }
+        }
+    }
+    if !assume_yes && !modes::confirm(format_args!("Delete {}?", path.display()))? {
+        return Err(AppError::NotDeleted(path.display().to_string()));
+    }
+
+    delete_entry(&path)?;
+    println!("Desktop entry deleted: {}", path.display());
+    Ok(())
+}
+
+/// Prints the `<name>.desktop` entry installed locally, or globally with `is_global`,
+/// as JSON with `as_json` (the format --from-json reads) and as a .desktop file otherwise
+pub fn run_export(is_global: bool, name: &str, as_json: bool, local_share_applications: &str, global_share_applications: &str) -> Result<(), AppError> {
+    // Like listing, exporting only reads so it doesn't need root
+    let dir = modes::applications_path(is_global, local_share_applications, global_share_applications)?;
+
+    let entry = read_entry(&dir, name)?;
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&entry)?);
+    } else {
+        print!("{}", entry);
+    }
+    Ok(())
+}
+
+/// Prints the path and contents of every file [`find_installed`] finds for `name`, as its keys
+/// and their unescaped values with `parsed`
+pub fn run_show(name: &str, parsed: bool, local_share_applications: &str, global_share_applications: &str) -> Result<(), AppError> {
+    let paths = find_installed(name, local_share_applications, global_share_applications)?;
+    if paths.is_empty() {
+        let dir = modes::applications_path(false, local_share_applications, global_share_applications)?;
+        return Err(AppError::NoMatchingEntry { name: name.to_string(), close_matches: close_matches(&dir, name)? });
+    }
+
+    for (i, path) in paths.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let note = match i {
+            _ if paths.len() == 1 => "",
+            0 => " (takes precedence)",
+            _ => " (hidden by the local one)",
+        };
+        println!("{}{}:", path.display(), note);
+
+        let contents = fs::read_to_string(path)?;
+        if parsed {
+            let pairs = DesktopEntry::from_str(&contents)?.key_values();
+            let key_width = pairs.iter().map(|(key, _)| key.chars().count()).max().unwrap_or(0);
+            for (key, value) in pairs {
+                println!("{:<key_width$}  {}", key, value);
+            }
+        } else {
+            print!("{}", contents);
+            if !contents.ends_with('\n') {
+                println!();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A problem --validate found in a file, with the line it's on when it comes down to one
+#[derive(Debug, PartialEq)]
+pub struct LintIssue {
+    pub severity: Severity,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// Checks every .desktop file given, directories for the .desktop files inside them, printing each
+/// problem as `path:line: error: message` and returning how many errors there were
+pub fn run_validate_files(paths: &[PathBuf]) -> Result<usize, AppError> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            files.extend(list_entries(path)?.into_iter().map(|(_, path)| path));
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let (mut errors, mut warnings) = (0, 0);
+    for file in &files {
+        let issues = match fs::read_to_string(file) {
+            Ok(contents) => lint(&contents),
+            Err(e) => vec![LintIssue { severity: Severity::Error, line: None, message: e.to_string() }],
+        };
+        for issue in issues {
+            let (severity, count) = match issue.severity {
+                Severity::Error => ("error", &mut errors),
+                Severity::Warning => ("warning", &mut warnings),
+            };
+            *count += 1;
+            match issue.line {
+                Some(line) => println!("{}:{}: {}: {}", file.display(), line, severity, issue.message),
+                None => println!("{}: {}: {}", file.display(), severity, issue.message),
+            }
+        }
+    }
+    println!("Checked {} file(s): {} error(s), {} warning(s)", files.len(), errors, warnings);
+    Ok(errors)
+}
+
+/// Parses the contents of a .desktop file and runs [`DesktopEntry::validate`] on it, each issue
+/// pointing at the line of the key it's about (none for a key that's missing)
+pub fn lint(contents: &str) -> Vec<LintIssue> {
+    let entry = match DesktopEntry::from_str(contents) {
+        Ok(entry) => entry,
+        Err(e) => return vec![LintIssue { severity: Severity::Error, line: e.line, message: e.message }],
+    };
+
+    let key_line = |key: &str| {
+        let mut in_entry = false;
+        contents.lines().position(|line| {
+            let line = line.trim();
+            if line.starts_with('[') {
+                in_entry = line == "[Desktop Entry]";
+                return false;
+            }
+            in_entry && line.split_once('=').is_some_and(|(line_key, _)| {
+                let line_key = line_key.trim();
+                line_key == key || line_key.strip_prefix(key).is_some_and(|rest| rest.starts_with('['))
+            })
+        }).map(|index| index + 1)
+    };
+    entry.validate()
+        .into_iter()
+        .map(|issue| LintIssue { severity: issue.severity, line: key_line(&issue.key), message: issue.message })
+        .collect()
+}
+
+/// The files `name` refers to: the file at the path `name`, or else the installed entry with that
+/// name or file name locally and globally, in the order launchers look them up (the local one wins)
+pub fn find_installed(name: &str, local_share_applications: &str, global_share_applications: &str) -> Result<Vec<PathBuf>, AppError> {
+    let target = Path::new(name);
+    if target.is_file() {
+        return Ok(vec![target.to_path_buf()]);
+    }
+
+    let mut paths = Vec::new();
+    for is_global in [false, true] {
+        let dir = modes::applications_path(is_global, local_share_applications, global_share_applications)?;
+        let path = entry_path(&dir, name.trim_end_matches(".desktop"));
+        if path.is_file() {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+/// The installed file for `name`, `<name>.desktop` when there is one (ex: a file name given as is)
+/// and the slugified name entries are written to otherwise
+fn entry_path(dir: &Path, name: &str) -> PathBuf {
+    let exact = dir.join(format!("{}.desktop", name.trim()));
+    if exact.exists() {
+        exact
+    } else {
+        dir.join(format!("{}.desktop", desktop_entry::slugify(name)))
+    }
+}
+
+/// Finds the file --edit refers to: a path to a .desktop file, or else the name or file name
+/// (ex: "My App" or my-app.desktop) of an entry installed locally, or globally with `is_global`
+pub fn locate_entry(target: &Path, is_global: bool, local_share_applications: &str, global_share_applications: &str) -> Result<PathBuf, AppError> {
+    if target.is_file() {
+        return Ok(target.to_path_buf());
+    }
+    // Something with a directory in it can only be a path
+    if target.components().count() > 1 {
+        return Err(AppError::EntryNotFound(target.display().to_string()));
+    }
+
+    let dir = modes::applications_path(is_global, local_share_applications, global_share_applications)?;
+    let name = target.to_string_lossy();
+    let path = entry_path(&dir, name.trim_end_matches(".desktop"));
+    if path.is_file() {
+        return Ok(path);
+    }
+    Err(AppError::NoMatchingEntry { name: name.to_string(), close_matches: close_matches(&dir, &name)? })
+}
+
+/// The file names of the entries in `dir` whose name or file name is close to `name`, closest first
+fn close_matches(dir: &Path, name: &str) -> io::Result<Vec<String>> {
+    let name = name.trim().trim_end_matches(".desktop").to_lowercase();
+    let mut matches: Vec<(f64, String)> = list_entries(dir)?
+        .into_iter()
+        .filter_map(|(entry_name, path)| {
+            let file_name = path.file_name()?.to_string_lossy().to_string();
+            let file_stem = path.file_stem()?.to_string_lossy().to_lowercase();
+            let score = [entry_name.to_lowercase(), file_stem]
+                .iter()
+                .map(|candidate| {
+                    if candidate.contains(&name) || name.contains(candidate.as_str()) {
+                        1.0
+                    } else {
+                        strsim::jaro_winkler(&name, candidate)
+                    }
+                })
+                .fold(0.0, f64::max);
+            (score >= 0.8).then_some((score, file_name))
+        })
+        .collect();
+    matches.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    Ok(matches.into_iter().take(3).map(|(_, file_name)| file_name).collect())
+}
+
+/// Parses the entry installed in `dir` for `name`
+pub fn read_entry(dir: &Path, name: &str) -> Result<DesktopEntry, AppError> {
+    let path = entry_path(dir, name);
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(AppError::EntryNotFound(path.display().to_string())),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(DesktopEntry::from_str(&contents)?)
+}
+
+/// Removes the entry at `path`, one that's already gone is an [`AppError::EntryNotFound`]
+pub fn delete_entry(path: &Path) -> Result<(), AppError> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Err(AppError::EntryNotFound(path.display().to_string())),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Returns the `Name` and path of every .desktop file in `dir`, sorted by file name.
+/// A directory that doesn't exist has no entries rather than being an error, and a file that
+/// can't be read (ex: a broken symlink) is skipped with a warning.
+pub fn list_entries(dir: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let read_dir = match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut entries = Vec::new();
+    for dir_entry in read_dir {
+        let path = dir_entry?.path();
+        if path.extension().is_none_or(|extension| extension != "desktop") {
+            continue;
+        }
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                eprintln!("Warning: {} couldn't be read, skipping it: {}", path.display(), e);
+                continue;
+            }
+        };
+        let name = contents.lines()
+            .find_map(|line| line.trim().strip_prefix("Name="))
+            .unwrap_or_default()
+            .to_string();
+        entries.push((name, path));
+    }
+
+    entries.sort_by(|(_, a), (_, b)| a.cmp(b));
+    Ok(entries)
+}
@@ -1,221 +1,786 @@
+use std::fmt;
+use std::fs;
 use std::fs::File;
-use std::io::Write;
-use std::sync::{Arc, Mutex};
-use gtk::Application;
-use gtk::prelude::{ApplicationExt, ApplicationExtManual};
-use crate::{build_ui, desktop_entry, flags, user_details, AppState};
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::{Deserialize, Deserializer, Serialize};
+use crate::{categories, desktop_entry, desktop_utils, flags, manage, path, user_details, AppState};
+use crate::cli::{CliArgs, EntryArgs};
+use crate::user_details::Answers;
+use crate::desktop_entry::{AppType, DesktopEntry, DesktopEntryBuilder, DesktopEntrySpec, Severity};
+use crate::desktop_utils::ExecChmod;
+use crate::app_error::AppError;
+use crate::error::CreateDesktopError;
+
+pub fn run_cli(is_global: bool, args: Vec<String>, local_share_applications: &str, global_share_applications: &str, default_config: Option<&Path>) -> Result<(), AppError> {
+
+    let mut cli_args = CliArgs::from_args(&args)?;
+    if cli_args.stdin {
+        let mut answers = String::new();
+        io::stdin().read_to_string(&mut answers)?;
+        cli_args = CliArgs::from_args_and_answers(&args, &answers)?;
+    }
+    cli_args.load_defaults(default_config)?;
+    let args = cli_args;
+    let global_share_applications = &args.global_dir(global_share_applications);
 
-pub fn run_cli(is_global: bool, args: Vec<String>, local_share_applications: &str, global_share_applications: &str) -> std::io::Result<()> {
+    // Previewing doesn't write anything, so it doesn't need root
+    let batch_dir = || if args.dry_run {
+        applications_path(is_global, local_share_applications, global_share_applications)
+    } else {
+        applications_dir(is_global, local_share_applications, global_share_applications)
+    };
+    if let Some(json_path) = &args.from_json {
+        let applications_dir = batch_dir()?;
+        let (specs, is_array) = json_specs(&read_source(json_path)?, json_path)?;
+        args.explain(format_args!("Creating {} entries from {} in {}", specs.len(), json_path.display(), applications_dir.display()));
+        return create_from_specs(specs, json_path, &applications_dir, is_array, is_global, &args);
+    }
+    if let Some(toml_path) = &args.from_toml {
+        let applications_dir = batch_dir()?;
+        let specs = toml_specs(&read_source(toml_path)?)?;
+        args.explain(format_args!("Creating {} entries from {} in {}", specs.len(), toml_path.display(), applications_dir.display()));
+        return create_from_specs(specs, toml_path, &applications_dir, true, is_global, &args);
+    }
 
-    let has_name = args.iter().any(|arg| arg == flags::NAME);
-    let has_desktop_flags = args.iter().any(|arg|
-        arg == flags::COMMENT ||
-            arg == flags::EXEC_PATH ||
-            arg == flags::ICON_PATH ||
-            arg == flags::TERMINAL_APP ||
-            arg == flags::APP_TYPE ||
-            arg == flags::CATEGORIES
-    );
+    // --stdout doesn't touch the filesystem, --output overrides the destination entirely and
+    // --edit writes back to the file being edited, so --local / --global don't apply to any of them
+    // `--output -` is the same as --stdout
+    let output = args.output.clone().filter(|output| output != Path::new("-"));
+    let to_stdout = args.stdout || args.output.as_deref() == Some(Path::new("-"));
+    let dry_run = args.dry_run;
+    let validate = args.validates_output();
+    let edit_path = match &args.edit {
+        Some(target) => Some(manage::locate_entry(target, is_global, local_share_applications, global_share_applications)?),
+        None => None,
+    };
+    let applications_dir = if to_stdout || output.is_some() || edit_path.is_some() {
+        PathBuf::new()
+    } else if dry_run {
+        // Nothing is written, so previewing a global entry doesn't need root
+        applications_path(is_global, local_share_applications, global_share_applications)?
+    } else {
+        applications_dir(is_global, local_share_applications, global_share_applications)?
+    };
+    if !applications_dir.as_os_str().is_empty() {
+        args.explain(format_args!("Installing {} into {}", if is_global { "globally" } else { "locally" }, applications_dir.display()));
+    }
 
-    // If desktop flags are present but no --name, panic
-    if has_desktop_flags && !has_name {
-        panic!("Need to specify {} alongside passing details. Try again. Exiting.", flags::NAME);
+    let entry = match &edit_path {
+        // Nothing to change was given, so the details are asked for starting out from the file's values
+        Some(edit_path) if args.name.is_none() && !args.has_details() && !args.stdin && can_prompt() => {
+            let existing = DesktopEntry::from_str(&fs::read_to_string(edit_path)?)?;
+            let answers = user_details::ask_interactively(Answers::from(&existing))?;
+            user_details::edit_from_answers(existing, answers)?
+        }
+        Some(edit_path) => edit_entry(edit_path, &args)?,
+        None => build_entry(&args)?,
+    };
+    let entry = with_header(entry, &args);
+    args.explain(format_args!("Built the entry:\n{}", entry.to_string().trim_end()));
+
+    check_entry(&entry, &args)?;
+    if !dry_run && !to_stdout {
+        chmod_exec(&entry, &args)?;
     }
 
-    // Get home directory
-    let mut path = dirs::home_dir()
-        .expect("Failed to get home directory");
+    // A missing program is the most common mistake, but it may only be installed later, so it's only a warning
+    if !args.no_verify {
+        if let Some(warning) = entry.value("Exec").and_then(|exec| desktop_entry::verify_exec(&exec)) {
+            eprintln!("Warning: {}", warning);
+        }
+        if let Some(icon) = entry.value("Icon")
+            && desktop_entry::check_icon(&icon) == desktop_entry::IconCheck::ThemeName {
+            eprintln!("Note: Icon {} isn't a path, it will be looked up in the icon theme when the entry is shown", icon);
+        }
+    }
 
-    // Check if the user wants to install the desktop entry globally
-    if is_global {
-        // Check if running with sudo
-        if !nix::unistd::getuid().is_root() {
-            panic!("Global installation requires root privileges. Please run with sudo.");
+    if to_stdout {
+        if args.export_json {
+            print_json(&ExportedEntry::new(&entry, None, false))?;
+        } else {
+            print!("{}", entry);
         }
-        path.push(global_share_applications);
-    } else {
-        path.push(local_share_applications);
+        if validate {
+            desktop_utils::validate_entry(&entry)?;
+        }
+        return Ok(());
     }
 
-    // Create variables as containers for user input
-    let mut name = String::new();
-    let mut comment = String::new();
-    let mut exec_path = String::new();
-    let mut icon_path = String::new();
-    let mut terminal_app = String::new();
-    let mut app_type = String::new();
-    let mut categories = String::new();
+    // Create and write the desktop entry, an --output directory gets the usual file name
+    let file_name = file_name(&entry, &args)?;
+    let output = output.map(|output| {
+        if output.is_dir() || output.as_os_str().to_string_lossy().ends_with('/') {
+            output.join(&file_name)
+        } else {
+            output
+        }
+    });
+    let path = output
+        .or_else(|| edit_path.clone())
+        .unwrap_or_else(|| applications_dir.join(file_name));
+    let is_editing_in_place = edit_path.as_ref() == Some(&path);
+    if is_editing_in_place {
+        args.explain(format_args!("Writing back to the edited file {}", path.display()));
+    } else {
+        args.explain(format_args!("Writing to {}", path.display()));
+    }
 
+    if dry_run {
+        if args.export_json {
+            print_json(&ExportedEntry::new(&entry, Some(&path), false))?;
+        } else {
+            print!("{}", dry_run_preview(&path, &entry));
+        }
+        return Ok(());
+    }
 
+    // D-Bus activation looks the entry up by its bus name, so the file has to be named after it
+    let file_stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+    if entry.is_dbus_activatable() && !desktop_entry::is_reverse_dns_name(file_stem) {
+        eprintln!(
+            "Warning: {} is not a reverse DNS name (ex: org.example.App.desktop), D-Bus activation will not find it",
+            path.display()
+        );
+    }
 
-    let arg_name_value: Option<String> = args.iter()
-        .position(|arg: &String| arg == flags::NAME)
-        .and_then(|index| args.get(index + 1))
-        .map(|value: &String| value.to_string());
+    let existing = existing_entry(&path);
 
-    if let Some(_name) = &arg_name_value {
-        // --name is provided, so .desktop details will be provided by flags / arguments
-        // println!("Name provided via flag");
-        name = _name.to_string();
+    if !is_editing_in_place && !needs_writing(&path, &entry, existing.as_ref(), &args)? {
+        if args.export_json {
+            print_json(&ExportedEntry::new(&entry, Some(&path), false))?;
+        }
+        return Ok(());
+    }
 
-        // println!("Name provided: {}", _name);
+    // The keys of an edited file that weren't changed are written back as they were
+    let contents = match &edit_path {
+        Some(edit_path) => entry.to_string_keeping(&fs::read_to_string(edit_path)?),
+        None => entry.to_string(),
+    };
+    write_entry(&path, &entry, existing.as_ref(), &contents, &args)?;
+    if is_editing_in_place {
+        args.report(format_args!("Desktop entry updated at: {}", path.display()));
+    } else {
+        args.report(format_args!("Desktop entry created at: {}", path.display()));
+    }
 
-        let arg_comment_value: Option<String> = args.iter()
-            .position(|arg: &String| arg == flags::COMMENT)
-            .and_then(|index| {
-                // Collect all arguments after --comment until the next flag (starts with --)
-                let mut comment_parts = Vec::new();
-                let mut current_index = index + 1;
+    if validate {
+        desktop_utils::validate_file(&path)?;
+    }
+    if args.export_json {
+        print_json(&ExportedEntry::new(&entry, Some(&path), true))?;
+    }
+    // A global install is refreshed by default, an --output or edited file only when asked to
+    if args.refreshes(is_global && !applications_dir.as_os_str().is_empty())
+        && let Some(parent) = path.parent() {
+        refresh(parent, &args);
+    }
 
-                while let Some(arg) = args.get(current_index) {
-                    if arg.starts_with("--") {
-                        break;
-                    }
-                    comment_parts.push(arg);
-                    current_index += 1;
-                }
+    Ok(())
+}
 
-                if comment_parts.is_empty() {
-                    None
-                } else {
-                    Some(comment_parts.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(" "))
-                }
-            });
+/// Whether `entry` has to be written to `path`, false when the file there is already the same entry.
+/// An existing (possibly hand-edited) file that's different isn't clobbered unless --force is given
+/// or the user agrees to overwrite it
+fn needs_writing(path: &Path, entry: &DesktopEntry, existing: Option<&DesktopEntry>, args: &CliArgs) -> Result<bool, AppError> {
+    if !path.exists() || args.force {
+        return Ok(true);
+    }
+    if existing == Some(entry) {
+        args.report(format_args!("Desktop entry already up to date at: {}", path.display()));
+        return Ok(false);
+    }
+    // Standard input has been read to the end with --stdin, so there's no answer to wait for
+    if args.stdin || !confirm(format_args!("{} already exists, overwrite it?", path.display()))? {
+        return Err(AppError::FileExists(path.display().to_string()));
+    }
+    Ok(true)
+}
 
-        if let Some(_comment) = &arg_comment_value {
-            // println!("Comment provided: {}", _comment);
-            comment = arg_comment_value.unwrap();
+/// Writes `contents` to `path`, saying what it changes of the `existing` entry there
+fn write_entry(path: &Path, entry: &DesktopEntry, existing: Option<&DesktopEntry>, contents: &str, args: &CliArgs) -> Result<(), AppError> {
+    if let Some(existing) = existing {
+        let changes = existing.diff(entry);
+        if changes.is_empty() {
+            args.report(format_args!("No keys changed in {}", path.display()));
+        }
+        for change in changes {
+            args.report(change);
         }
+    }
 
-        let arg_exec_path_value: Option<String> = args.iter()
-            .position(|arg: &String| arg == flags::EXEC_PATH)
-            .and_then(|index| args.get(index + 1))
-            .map(|value: &String| value.to_string());
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = File::create(path)?;
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
 
-        if let Some(_exec_path) = &arg_exec_path_value {
-            // println!("Executable path provided: {}", _exec_path);
-            exec_path = arg_exec_path_value.unwrap();
-        }
+/// The entry at `path`, None when there's no file or it doesn't parse. A file that doesn't
+/// parse is treated as a different entry and replaced as a whole
+fn existing_entry(path: &Path) -> Option<DesktopEntry> {
+    fs::read_to_string(path).ok().and_then(|contents| DesktopEntry::from_str(&contents).ok())
+}
 
-        let arg_icon_path_value: Option<String> = args.iter()
-            .position(|arg: &String| arg == flags::ICON_PATH)
-            .and_then(|index| args.get(index + 1))
-            .map(|value: &String| value.to_string());
+/// Makes the program of the entry executable with --chmod-exec, warning when it's left alone
+fn chmod_exec(entry: &DesktopEntry, args: &CliArgs) -> Result<(), AppError> {
+    let Some(exec) = entry.value("Exec").filter(|_| args.chmod_exec) else {
+        return Ok(());
+    };
+    match desktop_utils::chmod_exec(&exec)? {
+        ExecChmod::Changed(path) => args.report(format_args!("Made {} executable", path.display())),
+        ExecChmod::Unchanged(path) => args.explain(format_args!("{} is already executable", path.display())),
+        ExecChmod::Skipped(reason) => eprintln!("Warning: didn't make the Exec program executable, {}", reason),
+    }
+    Ok(())
+}
 
-        if let Some(_icon_path) = &arg_icon_path_value {
-            // println!("Icon path provided: {}", _icon_path);
-            icon_path = arg_icon_path_value.unwrap();
-        }
+/// Refreshes the desktop database of `applications_dir`, saying so unless --quiet
+fn refresh(applications_dir: &Path, args: &CliArgs) {
+    if desktop_utils::refresh_desktop_database(applications_dir, |message| args.explain(message)) {
+        args.report(format_args!("Desktop database refreshed in {}", applications_dir.display()));
+    }
+}
 
-        let arg_terminal_value: Option<String> = args.iter()
-            .position(|arg: &String| arg == flags::TERMINAL_APP)
-            .and_then(|index| args.get(index + 1))
-            .map(|value: &String| value.to_string());
+/// What --export-json prints for an entry: the keys --from-json reads, where the file goes and whether it was written
+#[derive(Serialize)]
+pub struct ExportedEntry<'a> {
+    #[serde(flatten)]
+    entry: &'a DesktopEntry,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    destination: Option<&'a Path>,
+    written: bool,
+}
 
-        if let Some(_terminal_app) = &arg_terminal_value {
-            // println!("Terminal provided: {}", _terminal_app);
-            terminal_app = arg_terminal_value.unwrap();
-        }
+/// The keys ExportedEntry adds to an entry, read back by --from-json without a warning
+const EXPORTED_KEYS: [&str; 2] = ["destination", "written"];
 
-        let arg_app_type_value: Option<String> = args.iter()
-            .position(|arg: &String| arg == flags::APP_TYPE)
-            .and_then(|index| args.get(index + 1))
-            .map(|value: &String| value.to_string());
+impl<'a> ExportedEntry<'a> {
+    pub fn new(entry: &'a DesktopEntry, destination: Option<&'a Path>, written: bool) -> Self {
+        ExportedEntry { entry, destination, written }
+    }
+}
 
-        if let Some(_app_type) = &arg_app_type_value {
-            // println!("App type provided: {}", _app_type);
-            app_type = arg_app_type_value.unwrap();
-        }
+fn print_json(value: &impl Serialize) -> Result<(), AppError> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
 
-        let arg_categories_value: Option<String> = args.iter()
-            .position(|arg: &String| arg == flags::CATEGORIES)
-            .and_then(|index| args.get(index + 1))
-            .map(|value: &String| value.to_string());
+/// What --dry-run prints instead of writing `entry` to `path`
+pub fn dry_run_preview(path: &Path, entry: &DesktopEntry) -> String {
+    format!("Would write {}:\n{}", path.display(), entry)
+}
 
-        if let Some(_categories) = &arg_categories_value {
-            // println!("Categories provided: {}", _categories);
-            categories = arg_categories_value.unwrap();
-        }
+/// Prints the warnings of `entry` and fails on its errors. Errors always stop the entry
+/// from being written, warnings only with --strict.
+fn check_entry(entry: &DesktopEntry, args: &CliArgs) -> Result<(), AppError> {
+    let (errors, warnings): (Vec<_>, Vec<_>) = entry.validate()
+        .into_iter()
+        .partition(|issue| issue.severity == Severity::Error || args.strict);
+    args.explain(format_args!("Validation found {} error(s) and {} warning(s)", errors.len(), warnings.len()));
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    if !errors.is_empty() {
+        return Err(AppError::InvalidEntry(errors));
+    }
+    Ok(())
+}
+
+/// Asks a yes/no `question` when run from a terminal, elsewhere (ex: a script) there is
+/// no one to ask so the answer is no
+pub fn confirm(question: impl fmt::Display) -> Result<bool, AppError> {
+    if !can_prompt() {
+        return Ok(false);
+    }
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
+}
 
+/// The file name the entry is written to, --filename or --app-id when given and the slugified Name otherwise
+fn file_name(entry: &DesktopEntry, args: &CliArgs) -> Result<String, AppError> {
+    let Some(filename) = &args.filename else {
+        return Ok(match args.entry.app_id()? {
+            Some(app_id) => {
+                args.explain(format_args!("Naming the file after the app id {}", app_id));
+                format!("{}.desktop", app_id)
+            }
+            None => {
+                args.explain(format_args!("Naming the file {} after the Name {:?}", entry.file_name(), entry.name()));
+                entry.file_name()
+            }
+        });
+    };
+    let stem = filename.trim().trim_end_matches(".desktop");
+    if stem.is_empty() || stem.contains('/') {
+        return Err(CreateDesktopError::invalid_field(flags::FILENAME, filename).into());
+    }
+    args.explain(format_args!("Naming the file {}.desktop from {}", stem, flags::FILENAME));
+    Ok(format!("{}.desktop", stem))
+}
+
+/// Whether there's someone at a terminal to ask, which is never the case while testing
+fn can_prompt() -> bool {
+    !cfg!(test) && io::stdin().is_terminal()
+}
 
+/// Adds the "Created by" comment above the entry, unless --no-header is given
+fn with_header(entry: DesktopEntry, args: &CliArgs) -> DesktopEntry {
+    if args.no_header {
+        entry
     } else {
-        // --name has not been used, thus details will need to be provided by user through TUI
+        entry.with_generated_header()
+    }
+}
 
-        // println!("Ask user for details");
+/// A --from-toml file, an `[entry]` table or `[[entry]]` / `[[app]]` tables with the same keys as a --from-json object
+#[derive(Deserialize)]
+struct TomlApps {
+    #[serde(default)]
+    app: Vec<DesktopEntrySpec>,
+    #[serde(default)]
+    entry: TomlEntries,
+}
 
-        // Ask user to populate details for .desktop file
-        user_details::ask_user_to_fill_in_details(
-            &mut name,
-            &mut comment,
-            &mut exec_path,
-            &mut icon_path,
-            &mut terminal_app,
-            &mut app_type,
-            &mut categories
-        );
+impl TomlApps {
+    fn specs(self) -> Vec<DesktopEntrySpec> {
+        self.entry.0.into_iter().chain(self.app).collect()
+    }
+}
+
+/// The entries of a single `[entry]` table or of `[[entry]]` tables. Visited as they are rather
+/// than through an untagged enum, so errors keep their line and key path
+#[derive(Default)]
+struct TomlEntries(Vec<DesktopEntrySpec>);
+
+impl<'de> Deserialize<'de> for TomlEntries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for EntriesVisitor {
+            type Value = TomlEntries;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an [entry] table or [[entry]] tables")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                Ok(TomlEntries(vec![DesktopEntrySpec::deserialize(MapAccessDeserializer::new(map))?]))
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                Ok(TomlEntries(Vec::deserialize(SeqAccessDeserializer::new(seq))?))
+            }
+        }
+
+        deserializer.deserialize_any(EntriesVisitor)
+    }
+}
+
+/// The contents of a --from-json or --from-toml file, standard input for `-`
+fn read_source(source: &Path) -> Result<String, AppError> {
+    if source == Path::new("-") {
+        let mut contents = String::new();
+        io::stdin().read_to_string(&mut contents)?;
+        return Ok(contents);
+    }
+    Ok(fs::read_to_string(source)?)
+}
 
+/// The entries of a --from-toml file, an error names the key it's about (ex: `entry.terminal`)
+pub fn toml_specs(toml: &str) -> Result<Vec<DesktopEntrySpec>, AppError> {
+    let apps: TomlApps = serde_path_to_error::deserialize(toml::Deserializer::new(toml))?;
+    Ok(apps.specs())
+}
 
+/// How a --from-json or --from-toml source is referred to in messages
+fn source_name(source: &Path) -> String {
+    if source == Path::new("-") { String::from("standard input") } else { source.display().to_string() }
+}
+
+/// The entries of a --from-json file, an array of objects or a single one, and whether it was an array.
+/// Keys that aren't fields of an entry (ex: a typo) are warned about rather than silently dropped
+pub fn json_specs(json: &str, source: &Path) -> Result<(Vec<DesktopEntrySpec>, bool), AppError> {
+    let (objects, is_array) = match serde_json::from_str(json)? {
+        serde_json::Value::Array(objects) => (objects, true),
+        object => (vec![object], false),
+    };
+
+    let mut specs = Vec::new();
+    for (index, object) in objects.into_iter().enumerate() {
+        let mut unknown = Vec::new();
+        specs.push(serde_ignored::deserialize(object, |key| {
+            let key = key.to_string();
+            if !EXPORTED_KEYS.contains(&key.as_str()) {
+                unknown.push(key);
+            }
+        })?);
+        if unknown.is_empty() {
+            continue;
+        }
+        let location = if is_array { format!("entry {} of {}", index + 1, source_name(source)) } else { source_name(source) };
+        eprintln!("Warning: unknown keys in {} were ignored: {}", location, unknown.join(", "));
     }
+    Ok((specs, is_array))
+}
 
-    // Create and write the desktop entry
-    let filename = format!("{}.desktop", name.trim());
-    path.push(filename);
+/// Creates one .desktop file in `applications_dir` per entry read from `source`.
+/// When `is_batch`, a bad entry is reported and skipped and the others are still created. The flags
+/// describing the entry override the values read for each of them. Every entry is built and its
+/// destination checked before any file is written, so a refused overwrite doesn't leave half a batch behind.
+fn create_from_specs(specs: Vec<DesktopEntrySpec>, source: &Path, applications_dir: &Path, is_batch: bool, is_global: bool, args: &CliArgs) -> Result<(), AppError> {
+    let total = specs.len();
+    let mut failed = 0;
+    let mut planned: Vec<PlannedEntry> = Vec::new();
+    for (index, spec) in specs.into_iter().enumerate() {
+        match plan_spec_entry(spec, applications_dir, &planned, args) {
+            Ok(plan) => planned.push(plan),
+            // The error of a file with a single entry is the error, there's no batch to report on
+            Err(e) if !is_batch => return Err(e),
+            Err(e) => {
+                eprintln!("Error: entry {} of {} in {}: {}", index + 1, total, source_name(source), e);
+                failed += 1;
+            }
+        }
+    }
 
-    let mut file = File::create(&path)?;
-    let entry = desktop_entry::DesktopEntry::new(
-        name,
-        comment,
-        exec_path,
-        icon_path,
-        terminal_app,
-        app_type,
-        categories,
-    );
+    let mut created = Vec::new();
+    for plan in planned {
+        if args.dry_run {
+            if !args.export_json {
+                print!("{}", dry_run_preview(&plan.path, &plan.entry));
+            }
+            created.push((plan.path, plan.entry, false));
+            continue;
+        }
+        if !plan.needs_writing {
+            created.push((plan.path, plan.entry, false));
+            continue;
+        }
+        match write_spec_entry(&plan, args) {
+            Ok(()) => {
+                args.report(format_args!("Desktop entry created at: {}", plan.path.display()));
+                created.push((plan.path, plan.entry, true));
+            }
+            Err(e) if !is_batch => return Err(e),
+            Err(e) => {
+                eprintln!("Error: {}: {}", plan.path.display(), e);
+                failed += 1;
+            }
+        }
+    }
 
-    file.write_all(entry.to_string().as_bytes())?;
-    println!("Desktop entry created at: {}", path.to_str().unwrap());
+    if failed < total && args.refreshes(is_global) && !args.dry_run {
+        refresh(applications_dir, args);
+    }
+    if args.export_json {
+        let exported: Vec<_> = created.iter()
+            .map(|(path, entry, written)| ExportedEntry::new(entry, Some(path), *written))
+            .collect();
+        match exported.as_slice() {
+            [exported] if !is_batch => print_json(exported)?,
+            _ => print_json(&exported)?,
+        }
+    }
+    if failed > 0 {
+        return Err(AppError::BatchFailed { failed, total });
+    }
+    Ok(())
+}
 
+/// An entry of a --from-json or --from-toml file that has been built and checked, waiting to be written
+struct PlannedEntry {
+    path: PathBuf,
+    entry: DesktopEntry,
+    existing: Option<DesktopEntry>,
+    needs_writing: bool,
+}
+
+/// Builds the entry of `spec` and checks where it goes, the same way a single entry is checked.
+/// Two entries of the same file can't go to the same path, the second would replace the first
+fn plan_spec_entry(spec: DesktopEntrySpec, applications_dir: &Path, planned: &[PlannedEntry], args: &CliArgs) -> Result<PlannedEntry, AppError> {
+    let entry = spec.build()?;
+    let entry = if args.name.is_some() || args.has_details() { override_entry(entry, args)? } else { entry };
+    let entry = with_header(entry, args);
+    check_entry(&entry, args)?;
+
+    let path = applications_dir.join(entry.file_name());
+    if planned.iter().any(|plan| plan.path == path) {
+        return Err(AppError::FileExists(path.display().to_string()));
+    }
+    let existing = existing_entry(&path);
+    let needs_writing = args.dry_run || needs_writing(&path, &entry, existing.as_ref(), args)?;
+    Ok(PlannedEntry { path, entry, existing, needs_writing })
+}
+
+fn write_spec_entry(plan: &PlannedEntry, args: &CliArgs) -> Result<(), AppError> {
+    chmod_exec(&plan.entry, args)?;
+    write_entry(&plan.path, &plan.entry, plan.existing.as_ref(), &plan.entry.to_string(), args)?;
+    if args.validates_output() {
+        desktop_utils::validate_file(&plan.path)?;
+    }
     Ok(())
 }
 
-pub fn run_gui(local_share_applications: &str) -> std::io::Result<()> {
-    let state = Arc::new(Mutex::new(AppState::default()));
+/// Loads the entry at `path` and applies the flags in `args` over it,
+/// anything not given as a flag is kept as it was in the file
+pub fn edit_entry(path: &Path, args: &CliArgs) -> Result<DesktopEntry, AppError> {
+    override_entry(DesktopEntry::from_str(&fs::read_to_string(path)?)?, args)
+}
 
-    let app = Application::builder()
-        .application_id("com.catley.createdesktopfile")
-        .build();
+/// Applies the flags in `args` over `existing`, anything not given as a flag is kept as it was
+fn override_entry(existing: DesktopEntry, args: &CliArgs) -> Result<DesktopEntry, AppError> {
+    let details = &args.entry;
+    let is_link = match &details.app_type {
+        Some(app_type) => AppType::from_str(app_type)? == AppType::Link,
+        None => existing.value("Type").as_deref() == Some("Link"),
+    };
+    let mut builder = DesktopEntryBuilder::from(existing);
+
+    if let Some(name) = &args.name {
+        builder = builder.name(name);
+    }
+    if let Some(generic_name) = &details.generic_name {
+        builder = builder.generic_name(generic_name);
+    }
+    if let Some(comment) = details.comment() {
+        builder = builder.comment(comment);
+    }
+    if let Some(exec_path) = &details.exec_path {
+        builder = builder.exec(exec_path);
+    }
+    if let Some(field_code) = &details.field_code {
+        builder = builder.field_code(field_code);
+    }
+    if let Some(icon_path) = &details.icon_path {
+        builder = builder.icon(icon_path);
+    }
+    if let Some(terminal) = EntryArgs::terminal(details.terminal_app.as_deref().unwrap_or_default())? {
+        builder = builder.terminal(terminal);
+    }
+    if let Some(app_type) = &details.app_type {
+        builder = builder.app_type(AppType::from_str(app_type)?);
+    }
+    if let Some(url) = &details.url {
+        builder = builder.url(url);
+    } else if !is_link {
+        // Only a Link has a URL, so one left over from changing the type is dropped
+        builder = builder.url("");
+    }
+    if let Some(categories) = &details.categories {
+        builder = builder.categories(categories::parse_categories(categories));
+    }
+    if let Some(only_show_in) = &details.only_show_in {
+        builder = builder.only_show_in(only_show_in);
+    }
+    if let Some(not_show_in) = &details.not_show_in {
+        builder = builder.not_show_in(not_show_in);
+    }
+    if let Some(keywords) = &details.keywords {
+        builder = builder.keywords(desktop_entry::normalize_keywords(keywords)?);
+    }
+    if let Some(mime_type) = &details.mime_type {
+        builder = builder.mime_type(desktop_entry::normalize_mime_types(mime_type)?);
+    }
+    if let Some(startup_notify) = &details.startup_notify {
+        builder = builder.startup_notify(desktop_entry::parse_startup_notify(startup_notify)?);
+    }
+    if let Some(startup_wm_class) = details.startup_wm_class()? {
+        builder = builder.startup_wm_class(startup_wm_class);
+    }
+    if let Some(try_exec) = &details.try_exec {
+        builder = builder.try_exec(try_exec);
+    }
+    if let Some(working_dir) = &details.working_dir {
+        builder = builder.working_dir(working_dir);
+    }
+    if let Some(spec_version) = &details.spec_version {
+        builder = builder.spec_version(spec_version);
+    }
 
-    let state_clone = Arc::clone(&state);
-    app.connect_activate(move |app| build_ui(app, &state_clone));
+    // Boolean flags are only applied when given, `--hidden false` switches a key back off
+    if let Some(no_display) = EntryArgs::switch(flags::NO_DISPLAY, &details.no_display)? {
+        builder = builder.no_display(no_display);
+    }
+    if let Some(hidden) = EntryArgs::switch(flags::HIDDEN, &details.hidden)? {
+        builder = builder.hidden(hidden);
+    }
+    if let Some(dbus_activatable) = EntryArgs::switch(flags::DBUS_ACTIVATABLE, &details.dbus_activatable)? {
+        builder = builder.dbus_activatable(dbus_activatable);
+    }
+    if let Some(prefers_non_default_gpu) = EntryArgs::switch(flags::PREFERS_NON_DEFAULT_GPU, &details.prefers_non_default_gpu)? {
+        builder = builder.prefers_non_default_gpu(prefers_non_default_gpu);
+    }
+    if details.no_spec_version {
+        builder = builder.without_spec_version();
+    }
 
-    // Run the GUI application
-    app.run();
+    // Actions and translations are added to the ones already in the file
+    for action in &details.action {
+        builder = builder.action(desktop_entry::DesktopAction::parse(action)?);
+    }
+    for (locale, name) in EntryArgs::locale_pairs(flags::NAME_LOCALE, &details.name_locale)? {
+        builder = builder.localized_name(locale, name);
+    }
+    for (locale, generic_name) in EntryArgs::locale_pairs(flags::GENERIC_NAME_LOCALE, &details.generic_name_locale)? {
+        builder = builder.localized_generic_name(locale, generic_name);
+    }
+    for (locale, comment) in EntryArgs::locale_pairs(flags::COMMENT_LOCALE, &details.comment_locale)? {
+        builder = builder.localized_comment(locale, comment);
+    }
+    for spec in &details.extra_key {
+        let (key, value) = parse_extra_key(spec)?;
+        builder = builder.extension_key(key, value);
+    }
+
+    Ok(builder.build()?)
+}
 
-    // After GUI closes, process the results
-    let state_data = state.lock().unwrap();
+/// Builds the desktop entry from the flags in `args`, asking for the details through the TUI when --name isn't given
+pub fn build_entry(args: &CliArgs) -> Result<DesktopEntry, AppError> {
+    // If desktop flags are present but no --name, there is nothing to name the file after
+    if args.has_details() && args.name.is_none() {
+        return Err(CreateDesktopError::MissingName.into());
+    }
 
-    // Only proceed if the name is not empty (indicating the user submitted the form)
-    if !state_data.name.is_empty() {
-        let mut path = dirs::home_dir()
-            .expect("Failed to get home directory");
+    // Container for user input
+    let mut state = AppState::default();
 
-        path.push(local_share_applications); // GUI mode always uses local installation
-        path.push(format!("{}.desktop", state_data.name.trim()));
+    if let Some(name) = &args.name {
+        // --name is provided, so .desktop details will be provided by flags / arguments
+        let details = &args.entry;
+        state.name = name.to_string();
+        state.generic_name = details.generic_name.clone().unwrap_or_default();
+        state.comment = details.comment().unwrap_or_default();
+        state.exec_path = details.exec_path.clone().unwrap_or_default();
+        state.field_code = details.field_code.clone().unwrap_or_default();
+        state.icon_path = details.icon_path.clone().unwrap_or_default();
+        state.terminal_app = details.terminal_app.clone().unwrap_or_default();
+        state.app_type = details.app_type.clone().unwrap_or_default();
+        state.url = details.url.clone().unwrap_or_default();
+        state.categories = details.categories.clone().unwrap_or_default();
+        state.keywords = details.keywords.clone().unwrap_or_default();
+        state.only_show_in = details.only_show_in.clone().unwrap_or_default();
+        state.not_show_in = details.not_show_in.clone().unwrap_or_default();
+        state.mime_type = details.mime_type.clone().unwrap_or_default();
+        state.startup_notify = details.startup_notify.clone().unwrap_or_default();
+        state.startup_wm_class = details.startup_wm_class()?.unwrap_or_default();
+        state.working_dir = details.working_dir.clone().unwrap_or_default();
+        state.try_exec = details.try_exec.clone().unwrap_or_default();
+        state.spec_version = details.spec_version.clone().unwrap_or_default();
+        state.no_spec_version = details.no_spec_version;
+        // --action can be repeated, once per action
+        state.actions = details.action.clone();
+        // Localized flags take a locale and a value, and can be repeated once per locale
+        state.localized_names = EntryArgs::locale_pairs(flags::NAME_LOCALE, &details.name_locale)?;
+        state.localized_generic_names = EntryArgs::locale_pairs(flags::GENERIC_NAME_LOCALE, &details.generic_name_locale)?;
+        state.localized_comments = EntryArgs::locale_pairs(flags::COMMENT_LOCALE, &details.comment_locale)?;
+        // --extra-key X-Vendor-Key=value can be repeated, once per key
+        state.extra_keys = details.extra_key
+            .iter()
+            .map(|spec| parse_extra_key(spec))
+            .collect::<Result<_, _>>()?;
+        state.no_display = EntryArgs::switch(flags::NO_DISPLAY, &details.no_display)?.unwrap_or_default();
+        state.hidden = EntryArgs::switch(flags::HIDDEN, &details.hidden)?.unwrap_or_default();
+        state.dbus_activatable = EntryArgs::switch(flags::DBUS_ACTIVATABLE, &details.dbus_activatable)?.unwrap_or_default();
+        state.prefers_non_default_gpu = EntryArgs::switch(flags::PREFERS_NON_DEFAULT_GPU, &details.prefers_non_default_gpu)?.unwrap_or_default();
+
+    } else if args.interactive && io::stdin().is_terminal() {
+        return user_details::entry_from_answers(user_details::ask_interactively(args.defaults.answers())?);
+    } else {
+        // --name has not been used, thus details will need to be provided by user through TUI
 
-        let mut file = File::create(&path)?;
-        let entry = desktop_entry::DesktopEntry::new(
-            state_data.name.clone(),
-            state_data.comment.clone(),
-            state_data.exec_path.clone(),
-            state_data.icon_path.clone(),
-            state_data.terminal_app.clone(),
-            state_data.app_type.clone(),
-            state_data.categories.clone(),
-        );
+        // Ask user to populate details for .desktop file
+        user_details::ask_user_to_fill_in_details(&mut state, &mut io::stdin().lock())?;
+    }
+    args.defaults.fill_in(&mut state);
+    state.keywords = desktop_entry::normalize_keywords(&state.keywords)?;
+    state.mime_type = desktop_entry::normalize_mime_types(&state.mime_type)?;
+    let startup_notify = desktop_entry::parse_startup_notify(&state.startup_notify)?;
+    let terminal = EntryArgs::terminal(&state.terminal_app)?;
+    let app_type = AppType::from_str(&state.app_type)?;
+
+    let mut builder = DesktopEntry::builder()
+        .name(state.name)
+        .generic_name(state.generic_name)
+        .comment(state.comment)
+        .exec(state.exec_path)
+        .field_code(state.field_code)
+        .icon(state.icon_path)
+        .app_type(app_type)
+        .categories(categories::parse_categories(&state.categories))
+        .keywords(state.keywords)
+        .mime_type(state.mime_type)
+        .startup_notify(startup_notify)
+        .startup_wm_class(state.startup_wm_class)
+        .no_display(state.no_display)
+        .hidden(state.hidden)
+        .working_dir(state.working_dir)
+        .try_exec(state.try_exec)
+        .dbus_activatable(state.dbus_activatable)
+        .url(state.url)
+        .only_show_in(state.only_show_in)
+        .not_show_in(state.not_show_in)
+        .prefers_non_default_gpu(state.prefers_non_default_gpu);
+    if let Some(terminal) = terminal {
+        builder = builder.terminal(terminal);
+    }
+    if !state.spec_version.trim().is_empty() {
+        builder = builder.spec_version(state.spec_version);
+    }
+    if state.no_spec_version {
+        builder = builder.without_spec_version();
+    }
+    for action in &state.actions {
+        builder = builder.action(desktop_entry::DesktopAction::parse(action)?);
+    }
+    for (locale, name) in state.localized_names {
+        builder = builder.localized_name(locale, name);
+    }
+    for (locale, generic_name) in state.localized_generic_names {
+        builder = builder.localized_generic_name(locale, generic_name);
+    }
+    for (locale, comment) in state.localized_comments {
+        builder = builder.localized_comment(locale, comment);
+    }
+    for (key, value) in state.extra_keys {
+        builder = builder.extension_key(key, value);
+    }
+    Ok(builder.build()?)
+}
 
-        file.write_all(entry.to_string().as_bytes())?;
-        println!("Desktop entry created at: {}", path.to_str().unwrap());
+/// Splits an --extra-key value, `X-Vendor-Key=value`, on its first `=`
+fn parse_extra_key(spec: &str) -> Result<(String, String), AppError> {
+    spec.split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+        .ok_or_else(|| CreateDesktopError::invalid_field(flags::EXTRA_KEY, spec).into())
+}
+
+/// Resolves the applications directory entries are installed into,
+/// checking for root privileges when installing globally
+pub fn applications_dir(is_global: bool, local_share_applications: &str, global_share_applications: &str) -> Result<PathBuf, AppError> {
+    // Check if running with sudo, only root can write to the global directory
+    if is_global && !nix::unistd::getuid().is_root() {
+        return Err(AppError::NeedsRoot);
     }
 
-    Ok(())
-}
\ No newline at end of file
+    applications_path(is_global, local_share_applications, global_share_applications)
+}
+
+/// The applications directory entries go to, without checking it can be written to
+pub fn applications_path(is_global: bool, local_share_applications: &str, global_share_applications: &str) -> Result<PathBuf, AppError> {
+    // Check if the user wants to install the desktop entry globally
+    if is_global {
+        Ok(PathBuf::from(global_share_applications))
+    } else {
+        // Local entries follow $XDG_DATA_HOME
+        Ok(path::local_applications_dir(local_share_applications)?)
+    }
+}
@@ -1,2 +1,45 @@
-pub const LOCAL_SHARE_APPLICATIONS: &str = ".local/share/applications/";
-pub const GLOBAL_SHARE_APPLICATIONS: &str = "/usr/share/applications/";
\ No newline at end of file
+use std::env;
+use std::path::{Path, PathBuf};
+use crate::error::CreateDesktopError;
+
+/// Where local entries are installed, under the [`data_home`]
+pub const LOCAL_SHARE_APPLICATIONS: &str = "applications/";
+pub const GLOBAL_SHARE_APPLICATIONS: &str = "/usr/share/applications/";
+
+/// The global applications directory under an installation prefix (ex: /usr/local gives /usr/local/share/applications/)
+pub fn prefixed_applications_dir(prefix: &Path) -> String {
+    format!("{}/share/applications/", prefix.to_string_lossy().trim_end_matches('/'))
+}
+
+/// The user's home directory, which local entries are installed under
+pub fn home_dir() -> Result<PathBuf, CreateDesktopError> {
+    dirs::home_dir().ok_or(CreateDesktopError::NoHomeDir)
+}
+
+/// The user's data directory, $XDG_DATA_HOME when it's set to an absolute path and ~/.local/share otherwise
+pub fn data_home() -> Result<PathBuf, CreateDesktopError> {
+    data_home_from(env::var_os("XDG_DATA_HOME").map(PathBuf::from))
+}
+
+/// The data directory for the value `xdg_data_home` of $XDG_DATA_HOME, see [`data_home`]
+pub fn data_home_from(xdg_data_home: Option<PathBuf>) -> Result<PathBuf, CreateDesktopError> {
+    match xdg_data_home {
+        // The spec says a relative path is invalid and should be ignored
+        Some(data_home) if data_home.is_absolute() => Ok(data_home),
+        _ => Ok(home_dir()?.join(".local/share")),
+    }
+}
+
+/// The user's config directory, $XDG_CONFIG_HOME when it's set to an absolute path and ~/.config otherwise
+pub fn config_home() -> Result<PathBuf, CreateDesktopError> {
+    match env::var_os("XDG_CONFIG_HOME").map(PathBuf::from) {
+        Some(config_home) if config_home.is_absolute() => Ok(config_home),
+        _ => Ok(home_dir()?.join(".config")),
+    }
+}
+
+/// The directory local entries are installed into, `local_share_applications` (ex: [`LOCAL_SHARE_APPLICATIONS`])
+/// under the [`data_home`], ex: ~/.local/share/applications
+pub fn local_applications_dir(local_share_applications: &str) -> Result<PathBuf, CreateDesktopError> {
+    Ok(data_home()?.join(local_share_applications))
+}
\ No newline at end of file
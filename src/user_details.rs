@@ -1,38 +1,207 @@
-use std::io;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+use dialoguer::{Confirm, Input, MultiSelect, Select};
+use crate::{categories, AppState};
+use crate::desktop_entry::{AppType, DesktopEntry, DesktopEntryBuilder};
+use crate::app_error::AppError;
 
-pub fn ask_user_to_fill_in_details(mut name: &mut String, mut comment: &mut String, mut exec_path: &mut String, mut icon_path: &mut String, mut terminal_app: &mut String, mut app_type: &mut String, mut categories: &mut String) {
+/// Asks for each detail on its own line of `input`, normally standard input
+pub fn ask_user_to_fill_in_details(state: &mut AppState, input: &mut impl BufRead) -> io::Result<()> {
     println!("Enter the name of the application:");
-    io::stdin()
-        .read_line(&mut name)
-        .expect("Failed to read name");
+    state.name = read_answer(input)?;
+
+    println!("Enter a generic name for the application: (ex: Text Editor, leave blank to skip)");
+    state.generic_name = read_answer(input)?;
 
     println!("Enter the comments for the application:");
-    io::stdin()
-        .read_line(&mut comment)
-        .expect("Failed to read comment");
+    state.comment = read_answer(input)?;
 
     println!("Enter the path to the executable:");
-    io::stdin()
-        .read_line(&mut exec_path)
-        .expect("Failed to read exec path");
+    state.exec_path = read_answer(input)?;
 
     println!("Enter the path to the icon:");
-    io::stdin()
-        .read_line(&mut icon_path)
-        .expect("Failed to read icon path");
-
-    println!("Terminal app? (true/false):");
-    io::stdin()
-        .read_line(&mut terminal_app)
-        .expect("Failed to read terminal app");
-
-    println!("Enter the type of application: (ex: Application)");
-    io::stdin()
-        .read_line(&mut app_type)
-        .expect("Failed to read app type");
-
-    println!("Enter the categories for the application: (ex: Development;)");
-    io::stdin()
-        .read_line(&mut categories)
-        .expect("Failed to read categories");
-}
\ No newline at end of file
+    state.icon_path = read_answer(input)?;
+
+    println!("Enter the working directory to start the application in: (leave blank to skip)");
+    state.working_dir = read_answer(input)?;
+
+    println!("Terminal app? (true/false, yes/no, 1/0, leave blank for false):");
+    state.terminal_app = read_answer(input)?;
+
+    println!("Enter the type of application: (Application, Link or Directory, leave blank for Application)");
+    state.app_type = read_answer(input)?;
+
+    if state.app_type.eq_ignore_ascii_case("Link") {
+        println!("Enter the URL the link opens: (ex: https://example.com)");
+        state.url = read_answer(input)?;
+    }
+
+    println!("Enter the categories for the application: (ex: Development;IDE; or Development,IDE)");
+    state.categories = read_answer(input)?;
+
+    println!("Enter search keywords for the application: (ex: editor;text; leave blank to skip)");
+    state.keywords = read_answer(input)?;
+
+    println!("Enter the MIME types the application opens: (ex: text/plain;image/png; leave blank to skip)");
+    state.mime_type = read_answer(input)?;
+
+    println!("Enter the window class of the application: (ex: firefox, leave blank to skip)");
+    state.startup_wm_class = read_answer(input)?;
+    Ok(())
+}
+
+/// Reads one answer, without the line ending and the spaces around it
+fn read_answer(input: &mut impl BufRead) -> io::Result<String> {
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+/// What the user answered in the `--interactive` prompts
+#[derive(Debug, Default)]
+pub struct Answers {
+    pub name: String,
+    pub generic_name: String,
+    pub comment: String,
+    pub exec_path: String,
+    pub icon_path: String,
+    pub working_dir: String,
+    pub terminal: bool,
+    pub app_type: AppType,
+    pub url: String,
+    pub categories: Vec<String>,
+    pub keywords: String,
+}
+
+impl From<&DesktopEntry> for Answers {
+    /// The answers `entry` already has, so editing it starts out from its values
+    fn from(entry: &DesktopEntry) -> Self {
+        let value = |key: &str| entry.value(key).unwrap_or_default();
+        Answers {
+            name: value("Name"),
+            generic_name: value("GenericName"),
+            comment: value("Comment"),
+            exec_path: value("Exec"),
+            icon_path: value("Icon"),
+            working_dir: value("Path"),
+            terminal: entry.value("Terminal").as_deref() == Some("true"),
+            app_type: AppType::from_str(&value("Type")).unwrap_or_default(),
+            url: value("URL"),
+            categories: value("Categories").split(';').filter(|category| !category.is_empty()).map(str::to_string).collect(),
+            keywords: value("Keywords"),
+        }
+    }
+}
+
+/// Asks for the details with prompts that have defaults, can't be left invalid and offer the
+/// categories as a list to pick from. Needs a terminal, see `ask_user_to_fill_in_details` otherwise.
+/// The prompts start out with the `defaults` that are set (ex: the values of an edited entry).
+pub fn ask_interactively(defaults: Answers) -> Result<Answers, AppError> {
+    let name: String = text_input("Name of the application", &defaults.name)
+        .validate_with(|name: &String| if name.trim().is_empty() { Err("A name is required") } else { Ok(()) })
+        .interact_text()?;
+    // The program is usually named after the application (ex: Firefox runs firefox)
+    let program = name.trim().to_lowercase().replace(' ', "-");
+    let or_program = |value: String| if value.is_empty() { program.clone() } else { value };
+
+    let generic_name = text_input("Generic name (ex: Text Editor)", &defaults.generic_name).interact_text()?;
+    let comment = text_input("Comment", &defaults.comment).interact_text()?;
+    let app_type = AppType::ALL[Select::new()
+        .with_prompt("Type")
+        .items(&AppType::ALL)
+        .default(AppType::ALL.iter().position(|app_type| *app_type == defaults.app_type).unwrap_or(0))
+        .interact()?];
+    let (exec_path, url) = if app_type == AppType::Link {
+        let url = text_input("URL the link opens", &defaults.url)
+            .validate_with(|url: &String| if url.trim().is_empty() { Err("A URL is required for links") } else { Ok(()) })
+            .interact_text()?;
+        (String::new(), url)
+    } else {
+        (text_input("Command to run", &or_program(defaults.exec_path)).interact_text()?, String::new())
+    };
+    let icon_path = text_input("Icon (a path or an icon theme name)", &or_program(defaults.icon_path)).interact_text()?;
+    let working_dir = text_input("Working directory", &defaults.working_dir).interact_text()?;
+    let terminal = Confirm::new()
+        .with_prompt("Run in a terminal?")
+        .default(defaults.terminal)
+        .interact()?;
+    let picked = MultiSelect::new()
+        .with_prompt("Categories (space to pick, enter to confirm)")
+        .items(&categories::MAIN_CATEGORIES)
+        .defaults(&categories::MAIN_CATEGORIES.map(|category| defaults.categories.iter().any(|picked| picked == category)))
+        .interact()?;
+    let keywords = text_input("Search keywords (ex: editor;text;)", &defaults.keywords).interact_text()?;
+
+    Ok(Answers {
+        name,
+        generic_name,
+        comment,
+        exec_path,
+        icon_path,
+        working_dir,
+        terminal,
+        app_type,
+        url,
+        categories: picked.into_iter().map(|index| categories::MAIN_CATEGORIES[index].to_string()).collect(),
+        keywords,
+    })
+}
+
+/// A text prompt that can be left empty, or that keeps `default` when it's set
+fn text_input(prompt: &str, default: &str) -> Input<'static, String> {
+    let input = Input::new().with_prompt(prompt);
+    if default.is_empty() {
+        input.allow_empty(true)
+    } else {
+        input.default(default.to_string())
+    }
+}
+
+/// Builds the entry the answers describe
+pub fn entry_from_answers(answers: Answers) -> Result<DesktopEntry, AppError> {
+    Ok(DesktopEntry::builder()
+        .name(answers.name)
+        .generic_name(answers.generic_name)
+        .comment(answers.comment)
+        .exec(answers.exec_path)
+        .icon(answers.icon_path)
+        .working_dir(answers.working_dir)
+        .terminal(answers.terminal)
+        .app_type(answers.app_type)
+        .url(answers.url)
+        .categories(answers.categories)
+        .keywords(crate::desktop_entry::normalize_keywords(&answers.keywords)?)
+        .build()?)
+}
+
+/// Applies the answers over `existing`, keeping what the prompts don't ask about (ex: actions,
+/// translations, unknown keys and categories that aren't main categories)
+pub fn edit_from_answers(existing: DesktopEntry, answers: Answers) -> Result<DesktopEntry, AppError> {
+    let kept_categories: Vec<String> = Answers::from(&existing).categories
+        .into_iter()
+        .filter(|category| !categories::MAIN_CATEGORIES.contains(&category.as_str()))
+        .collect();
+    let had_terminal = existing.value("Terminal").is_some();
+    // An Exec answer left as it was is kept as written, quoting and all
+    let exec_changed = existing.value("Exec").unwrap_or_default() != answers.exec_path;
+
+    let mut builder = DesktopEntryBuilder::from(existing);
+    if exec_changed {
+        builder = builder.exec(answers.exec_path);
+    }
+    let mut builder = builder
+        .name(answers.name)
+        .generic_name(answers.generic_name)
+        .comment(answers.comment)
+        .icon(answers.icon_path)
+        .working_dir(answers.working_dir)
+        .app_type(answers.app_type)
+        .url(answers.url)
+        .categories(answers.categories.into_iter().chain(kept_categories))
+        .keywords(crate::desktop_entry::normalize_keywords(&answers.keywords)?);
+    // Terminal=false is the default, so it's only written when the file already had it
+    if answers.terminal || had_terminal {
+        builder = builder.terminal(answers.terminal);
+    }
+    Ok(builder.build()?)
+}
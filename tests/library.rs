@@ -0,0 +1,31 @@
+use std::str::FromStr;
+use create_desktop_file::{write_entry, AppType, CreateDesktopError, DesktopEntry};
+
+#[test]
+fn test_build_write_and_parse_back() {
+    let dir = tempfile::tempdir().unwrap();
+    let entry = DesktopEntry::builder()
+        .name("Foo")
+        .comment("Does foo things")
+        .exec("/usr/bin/foo")
+        .categories(["Utility"])
+        .build()
+        .unwrap();
+
+    let path = write_entry(&entry, &dir.path().join("applications")).unwrap();
+    assert_eq!(path.file_name().unwrap(), "foo.desktop");
+
+    let parsed = DesktopEntry::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(parsed, entry);
+    assert_eq!(parsed.value("Type").as_deref(), Some("Application"));
+    assert!(parsed.diff(&entry).is_empty());
+}
+
+#[test]
+fn test_errors_are_reported_to_callers() {
+    let missing_name = DesktopEntry::builder().exec("/usr/bin/foo").build();
+    assert!(matches!(missing_name, Err(CreateDesktopError::MissingName)));
+
+    assert!(matches!(AppType::from_str("Service"), Err(CreateDesktopError::UnknownType(_))));
+    assert!(DesktopEntry::from_str("Name=Foo").is_err());
+}